@@ -0,0 +1,115 @@
+//! Peak memory and time of streaming conversion compared to building a `String`, and
+//! checks that streaming memory stays flat for wide input and only grows with depth
+//!
+//! Run with `cargo bench --bench stream`
+
+use json2lua::{parse_to_writer, parse_with, Options};
+use serde_json::Value;
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	io,
+	sync::atomic::{AtomicUsize, Ordering},
+	time::Instant,
+};
+
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+		PEAK.fetch_max(current, Ordering::Relaxed);
+		unsafe { System.alloc(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+		unsafe { System.dealloc(ptr, layout) }
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Run closure and return how many bytes above the starting point were allocated at peak
+fn peak<T>(f: impl FnOnce() -> T) -> (T, usize) {
+	let base = CURRENT.load(Ordering::Relaxed);
+	PEAK.store(base, Ordering::Relaxed);
+	let result = f();
+	(result, PEAK.load(Ordering::Relaxed) - base)
+}
+
+fn wide(len: usize) -> String {
+	let items: Vec<String> = (0..len)
+		.map(|i| format!(r#"{{"id": {i}, "name": "item {i}", "tags": ["a", "b"], "weight": 1.5}}"#))
+		.collect();
+
+	format!("[{}]", items.join(","))
+}
+
+fn deep(depth: usize) -> String {
+	format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+}
+
+/// Print peak memory of the conversions and return the one of writing into `io::Sink`
+fn measure(name: &str, json: &str, options: &Options) -> usize {
+	// serde_json refuses to build values nested deeper than 128 levels
+	let (parsed, tree) = peak(|| serde_json::from_str::<Value>(json).is_ok());
+	let tree = match parsed {
+		true => tree.to_string(),
+		false => String::from("-"),
+	};
+
+	let start = Instant::now();
+	let (_, stream) = peak(|| parse_to_writer(json.as_bytes(), io::sink(), options).unwrap());
+	let elapsed = start.elapsed();
+
+	let (lua, string) = peak(|| parse_with(json, options).unwrap());
+
+	println!(
+		"{name:<12} input {:>9} B  output {:>9} B  tree {:>9} B  writer {:>7} B  string {:>9} B  {elapsed:?}",
+		json.len(),
+		lua.len(),
+		tree,
+		stream,
+		string,
+	);
+
+	stream
+}
+
+fn main() {
+	let wide: Vec<usize> = [1_000, 10_000, 100_000]
+		.into_iter()
+		.map(|len| measure(&format!("wide {len}"), &wide(len), &Options::default()))
+		.collect();
+
+	// Writing into a sink only holds the open tables, never the whole input or output
+	let flat = wide[0] + 1024;
+	assert!(
+		wide.iter().all(|&peak| peak <= flat),
+		"memory grows with input size: {wide:?}"
+	);
+
+	// Output grows with the square of depth as every line is indented
+	let depths = [1_000, 2_000, 4_000];
+
+	let deep: Vec<usize> = depths
+		.into_iter()
+		.map(|depth| measure(&format!("deep {depth}"), &deep(depth), &Options::default()))
+		.collect();
+
+	// Only the indentation of open tables grows, frames are on a stack growing outside the heap
+	assert!(
+		deep.windows(2).all(|pair| pair[0] < pair[1]),
+		"memory doesn't grow with depth: {deep:?}"
+	);
+	assert!(
+		deep.iter()
+			.zip(depths)
+			.all(|(&peak, depth)| peak <= flat + depth * 4),
+		"memory grows faster than depth: {deep:?}"
+	);
+}
@@ -0,0 +1,559 @@
+//! Deserialize Lua data tables directly into Rust data structures
+//!
+//! ```rust
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct Item {
+//! 	name: String,
+//! 	damage: u32,
+//! 	tags: Vec<String>,
+//! 	owner: Option<String>,
+//! }
+//!
+//! let lua = r#"{
+//! 	["name"] = "sword",
+//! 	["damage"] = 10,
+//! 	["tags"] = {
+//! 		"sharp",
+//! 	},
+//! 	["owner"] = nil,
+//! }"#;
+//!
+//! let item = Item {
+//! 	name: String::from("sword"),
+//! 	damage: 10,
+//! 	tags: vec![String::from("sharp")],
+//! 	owner: None,
+//! };
+//!
+//! assert_eq!(json2lua::from_lua_str::<Item>(lua).unwrap(), item);
+//! ```
+
+use serde::de::{self, value::StringDeserializer, DeserializeOwned, IntoDeserializer, Visitor};
+use serde_json::Number;
+use std::vec;
+
+use crate::{
+	parser::{into_sequence, Key, Kind, Node, Parser},
+	Error, Result,
+};
+
+/// Deserializer for Lua table constructors and other literals
+pub struct Deserializer {
+	node: Node,
+}
+
+impl Deserializer {
+	/// Parse Lua source, fails on syntax errors
+	pub fn parse(lua: &str) -> Result<Self> {
+		Ok(Self {
+			node: Parser::new(lua).parse()?,
+		})
+	}
+}
+
+/// Deserialize an instance of type `T` from a Lua data table
+pub fn from_lua_str<T: DeserializeOwned>(lua: &str) -> Result<T> {
+	T::deserialize(Deserializer::parse(lua)?)
+}
+
+/// Give position of the value to errors reported by `Deserialize` implementations
+fn locate(line: usize, column: usize) -> impl FnOnce(Error) -> Error {
+	move |err| match err {
+		Error::Message(message) => Error::Lua {
+			line,
+			column,
+			message,
+		},
+		err => err,
+	}
+}
+
+fn visit_number<'de, V: Visitor<'de>>(number: Number, visitor: V) -> Result<V::Value> {
+	if let Some(number) = number.as_u64() {
+		visitor.visit_u64(number)
+	} else if let Some(number) = number.as_i64() {
+		visitor.visit_i64(number)
+	} else {
+		visitor.visit_f64(number.as_f64().unwrap_or_default())
+	}
+}
+
+macro_rules! deserialize_integer {
+	($($method:ident)*) => {
+		$(
+			fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+				self.deserialize_integer(visitor)
+			}
+		)*
+	};
+}
+
+impl Deserializer {
+	/// Every Lua 5.1 number is a float, so accept integral floats where integers are expected
+	fn deserialize_integer<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		let (line, column) = (self.node.line, self.node.column);
+
+		match self.node.kind {
+			Kind::Number(ref number) if number.is_f64() => {
+				let float = number.as_f64().unwrap_or_default();
+
+				if float.fract() == 0.0 && float.abs() < 2f64.powi(63) {
+					visitor.visit_i64(float as i64)
+				} else {
+					visitor.visit_f64(float)
+				}
+				.map_err(locate(line, column))
+			}
+			_ => de::Deserializer::deserialize_any(self, visitor),
+		}
+	}
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+	type Error = Error;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		let Node { line, column, kind } = self.node;
+
+		match kind {
+			Kind::Nil => visitor.visit_unit(),
+			Kind::Bool(bool) => visitor.visit_bool(bool),
+			Kind::Number(number) => visit_number(number, visitor),
+			Kind::String(string) => visitor.visit_string(string),
+			Kind::Table(entries) => match into_sequence(entries) {
+				Ok(values) => visitor.visit_seq(SeqAccess::new(values)),
+				Err(entries) => visitor.visit_map(MapAccess::new(entries)),
+			},
+		}
+		.map_err(locate(line, column))
+	}
+
+	deserialize_integer! {
+		deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+		deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+	}
+
+	fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		let (line, column) = (self.node.line, self.node.column);
+
+		match self.node.kind {
+			Kind::Nil => visitor.visit_none(),
+			_ => visitor.visit_some(self),
+		}
+		.map_err(locate(line, column))
+	}
+
+	fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		let (line, column) = (self.node.line, self.node.column);
+
+		match self.node.kind {
+			Kind::Table(entries) => match into_sequence(entries) {
+				Ok(values) => visitor.visit_seq(SeqAccess::new(values)),
+				// Empty tables are ambiguous, let them be empty sequences
+				Err(entries) if entries.is_empty() => visitor.visit_seq(SeqAccess::new(Vec::new())),
+				Err(_) => Err(error_at(
+					line,
+					column,
+					"expected sequence, found table with keys",
+				)),
+			}
+			.map_err(locate(line, column)),
+			_ => self.deserialize_any(visitor),
+		}
+	}
+
+	fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_tuple_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		_len: usize,
+		visitor: V,
+	) -> Result<V::Value> {
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		let (line, column) = (self.node.line, self.node.column);
+
+		match self.node.kind {
+			Kind::Table(entries) => visitor
+				.visit_map(MapAccess::new(entries))
+				.map_err(locate(line, column)),
+			_ => self.deserialize_any(visitor),
+		}
+	}
+
+	fn deserialize_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		_fields: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value> {
+		self.deserialize_map(visitor)
+	}
+
+	fn deserialize_enum<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		_variants: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value> {
+		let Node { line, column, kind } = self.node;
+
+		match kind {
+			Kind::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+			Kind::Table(mut entries) if entries.len() == 1 => match entries.pop() {
+				Some((Key::String(variant), value)) => {
+					visitor.visit_enum(EnumAccess { variant, value })
+				}
+				_ => Err(error_at(
+					line,
+					column,
+					"expected enum variant name as the key",
+				)),
+			},
+			_ => Err(error_at(
+				line,
+				column,
+				"expected enum variant name or table with a single key",
+			)),
+		}
+		.map_err(locate(line, column))
+	}
+
+	fn deserialize_newtype_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		visitor: V,
+	) -> Result<V::Value> {
+		let (line, column) = (self.node.line, self.node.column);
+		visitor
+			.visit_newtype_struct(self)
+			.map_err(locate(line, column))
+	}
+
+	fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		let (line, column) = (self.node.line, self.node.column);
+
+		match self.node.kind {
+			Kind::String(string) => visitor
+				.visit_byte_buf(string.into_bytes())
+				.map_err(locate(line, column)),
+			_ => self.deserialize_any(visitor),
+		}
+	}
+
+	fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		self.deserialize_bytes(visitor)
+	}
+
+	fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		visitor.visit_unit()
+	}
+
+	serde::forward_to_deserialize_any! {
+		bool i128 u128 f32 f64 char str string unit unit_struct identifier
+	}
+}
+
+fn error_at(line: usize, column: usize, message: &str) -> Error {
+	Error::Lua {
+		line,
+		column,
+		message: message.to_owned(),
+	}
+}
+
+struct SeqAccess {
+	values: vec::IntoIter<Node>,
+}
+
+impl SeqAccess {
+	fn new(values: Vec<Node>) -> Self {
+		Self {
+			values: values.into_iter(),
+		}
+	}
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+	type Error = Error;
+
+	fn next_element_seed<T: de::DeserializeSeed<'de>>(
+		&mut self,
+		seed: T,
+	) -> Result<Option<T::Value>> {
+		match self.values.next() {
+			Some(node) => seed.deserialize(Deserializer { node }).map(Some),
+			None => Ok(None),
+		}
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		Some(self.values.len())
+	}
+}
+
+struct MapAccess {
+	entries: vec::IntoIter<(Key, Node)>,
+	value: Option<Node>,
+}
+
+impl MapAccess {
+	fn new(entries: Vec<(Key, Node)>) -> Self {
+		Self {
+			entries: entries.into_iter(),
+			value: None,
+		}
+	}
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+	type Error = Error;
+
+	fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+		match self.entries.next() {
+			Some((key, value)) => {
+				let (line, column) = (value.line, value.column);
+
+				self.value = Some(value);
+
+				seed.deserialize(KeyDeserializer { key })
+					.map(Some)
+					.map_err(locate(line, column))
+			}
+			None => Ok(None),
+		}
+	}
+
+	fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+		match self.value.take() {
+			Some(node) => seed.deserialize(Deserializer { node }),
+			None => Err(de::Error::custom("value is missing")),
+		}
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		Some(self.entries.len())
+	}
+}
+
+struct EnumAccess {
+	variant: String,
+	value: Node,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess {
+	type Error = Error;
+	type Variant = Deserializer;
+
+	fn variant_seed<V: de::DeserializeSeed<'de>>(
+		self,
+		seed: V,
+	) -> Result<(V::Value, Deserializer)> {
+		let variant: StringDeserializer<Error> = self.variant.into_deserializer();
+		let variant = seed.deserialize(variant)?;
+		Ok((variant, Deserializer { node: self.value }))
+	}
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer {
+	type Error = Error;
+
+	fn unit_variant(self) -> Result<()> {
+		de::Deserialize::deserialize(self)
+	}
+
+	fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+		seed.deserialize(self)
+	}
+
+	fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+		de::Deserializer::deserialize_seq(self, visitor)
+	}
+
+	fn struct_variant<V: Visitor<'de>>(
+		self,
+		_fields: &'static [&'static str],
+		visitor: V,
+	) -> Result<V::Value> {
+		de::Deserializer::deserialize_map(self, visitor)
+	}
+}
+
+/// Lua keys can be integers, so let them become strings or numbers as needed
+struct KeyDeserializer {
+	key: Key,
+}
+
+macro_rules! deserialize_key_integer {
+	($($method:ident)*) => {
+		$(
+			fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+				match self.key {
+					Key::Integer(key) => visitor.visit_i64(key),
+					Key::String(key) => match key.parse::<i64>() {
+						Ok(key) => visitor.visit_i64(key),
+						Err(_) => visitor.visit_string(key),
+					},
+				}
+			}
+		)*
+	};
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+	type Error = Error;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		match self.key {
+			Key::Integer(key) => visitor.visit_i64(key),
+			Key::String(key) => visitor.visit_string(key),
+		}
+	}
+
+	fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		visitor.visit_string(self.key.to_string())
+	}
+
+	fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		self.deserialize_str(visitor)
+	}
+
+	fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		self.deserialize_str(visitor)
+	}
+
+	fn deserialize_newtype_struct<V: Visitor<'de>>(
+		self,
+		_name: &'static str,
+		visitor: V,
+	) -> Result<V::Value> {
+		visitor.visit_newtype_struct(self)
+	}
+
+	deserialize_key_integer! {
+		deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+		deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+	}
+
+	serde::forward_to_deserialize_any! {
+		bool i128 u128 f32 f64 char bytes byte_buf option unit unit_struct seq tuple
+		tuple_struct map struct enum ignored_any
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use serde::Deserialize;
+	use std::collections::HashMap;
+
+	use super::from_lua_str;
+	use crate::Error;
+
+	#[derive(Deserialize, Debug, PartialEq)]
+	enum Shape {
+		Empty,
+		Circle(f64),
+		Point(i32, i32),
+		Rect { w: u8, h: u8 },
+	}
+
+	#[derive(Deserialize, Debug, PartialEq)]
+	struct Config {
+		name: String,
+		size: (u16, u16),
+		shapes: Vec<Shape>,
+		lookup: HashMap<u32, String>,
+		empty: Vec<bool>,
+		missing: Option<u8>,
+	}
+
+	#[test]
+	fn typed() {
+		let lua = r#"return {
+	name = 'config',
+	size = { 1920, 1080.0 },
+	shapes = {
+		"Empty",
+		{ Circle = 1.5 },
+		{ Point = { 1, -2 } },
+		{ Rect = { w = 3, h = 4 } },
+	},
+	lookup = { [10] = "ten", ["20"] = "twenty" },
+	empty = {},
+}"#;
+
+		let config = Config {
+			name: String::from("config"),
+			size: (1920, 1080),
+			shapes: vec![
+				Shape::Empty,
+				Shape::Circle(1.5),
+				Shape::Point(1, -2),
+				Shape::Rect { w: 3, h: 4 },
+			],
+			lookup: HashMap::from([(10, String::from("ten")), (20, String::from("twenty"))]),
+			empty: Vec::new(),
+			missing: None,
+		};
+
+		assert_eq!(from_lua_str::<Config>(lua).unwrap(), config);
+	}
+
+	#[test]
+	fn error_position() {
+		let lua = "{\n\tname = 'config',\n\tsize = { 1920, -1 },\n}";
+
+		let err = from_lua_str::<Config>(lua).unwrap_err();
+
+		assert!(
+			matches!(
+				err,
+				Error::Lua {
+					line: 3,
+					column: 17,
+					..
+				}
+			),
+			"{err}"
+		);
+
+		let err = from_lua_str::<Config>("{ name = 'config' }").unwrap_err();
+
+		assert!(
+			matches!(
+				err,
+				Error::Lua {
+					line: 1,
+					column: 1,
+					..
+				}
+			),
+			"{err}"
+		);
+	}
+
+	#[test]
+	fn nesting_limit() {
+		let lua = format!("{}{}", "{".repeat(200_000), "}".repeat(200_000));
+		let err = from_lua_str::<serde_json::Value>(&lua).unwrap_err();
+
+		assert!(
+			matches!(
+				err,
+				Error::Lua {
+					line: 1,
+					column: 201,
+					..
+				}
+			),
+			"{err}"
+		);
+	}
+}
@@ -0,0 +1,555 @@
+use serde_json::{map, Number, Value};
+use std::{
+	fmt::{self, Write},
+	io, iter, slice, vec,
+};
+
+use crate::{
+	escape::write_quoted,
+	types::{Dialect, Type, Writer},
+	Annotation, Error, KeyOrder, KeyStyle, Layout, NullPolicy, Options, Result, Wrapper,
+};
+
+#[derive(Clone, Copy)]
+pub(crate) enum Key<'v> {
+	Positional,
+	Index(usize),
+	Name(&'v str),
+}
+
+/// Columns a tab counts as when measuring line width
+const TAB_WIDTH: usize = 4;
+
+/// Writes JSON values as Lua with a single indentation buffer reused for every line
+///
+/// Open tables are kept on an explicit stack rather than the call stack, so nesting
+/// depth is only limited by [`Options::max_depth`].
+///
+/// With [`Layout::Width`] every table is a group in the Wadler sense: it is written on
+/// one line when its flat form fits in the remaining width and broken over multiple
+/// lines otherwise, letting nested tables decide for themselves.
+pub(crate) struct Emitter<'a, W> {
+	writer: Column<W>,
+	options: &'a Options,
+	indent: String,
+	/// Whether tables are currently written on a single line
+	flat: bool,
+}
+
+impl<'a, W: Write> Emitter<'a, W> {
+	pub(crate) fn new(writer: W, options: &'a Options) -> Self {
+		Self {
+			writer: Column { writer, column: 0 },
+			options,
+			indent: String::new(),
+			flat: false,
+		}
+	}
+
+	/// Write the value wrapped in the chunk selected by [`Options::wrapper`], preceded by
+	/// type definitions selected by [`Options::annotation`]
+	pub(crate) fn chunk(&mut self, value: &Value) -> Result<()> {
+		let ty = match self.options.annotation {
+			Annotation::None => None,
+			_ => Some(Type::of(value)),
+		};
+
+		self.wrap(ty.as_ref(), |emitter| emitter.expression(value))
+	}
+
+	/// Write the expression wrapped in the chunk selected by [`Options::wrapper`]
+	///
+	/// Type definitions are only written when the type is given, callers infer it
+	/// whenever [`Options::annotation`] is set.
+	pub(crate) fn wrap(
+		&mut self,
+		ty: Option<&Type>,
+		expression: impl FnOnce(&mut Self) -> Result<()>,
+	) -> Result<()> {
+		let options = self.options;
+		let newline = options.line_break();
+		let assignment = options.assignment();
+
+		// Statements still need some whitespace between them
+		let gap = match options.is_compact() {
+			true => " ",
+			false => newline,
+		};
+
+		let annotation = match (&options.annotation, ty) {
+			(_, None) | (Annotation::None, _) => None,
+			(Annotation::Luau(name), _) => Some((Dialect::Luau, name.as_str())),
+			(Annotation::LuaLS(name), _) => Some((Dialect::LuaLS, name.as_str())),
+			(Annotation::Teal(name), _) => Some((Dialect::Teal, name.as_str())),
+		};
+
+		if let (Some((dialect, name)), Some(ty)) = (annotation, ty) {
+			Writer::new(&mut self.writer, options).declare(ty, name, dialect)?;
+
+			match dialect {
+				// Comments end with the line
+				Dialect::LuaLS => write!(self.writer, "{}{newline}", options.newline.as_str())?,
+				_ => write!(self.writer, "{gap}{newline}")?,
+			}
+		}
+
+		let data = Wrapper::Local(String::from("data"));
+
+		let wrapper = match (&options.wrapper, annotation) {
+			// Declarations make this a chunk, so a bare expression would not load
+			(Wrapper::Expression, Some((Dialect::Luau, _))) => &Wrapper::Return,
+			// Only variables can be typed
+			(Wrapper::Expression | Wrapper::Return, Some((Dialect::LuaLS | Dialect::Teal, _))) => {
+				&data
+			}
+			(wrapper, _) => wrapper,
+		};
+
+		match wrapper {
+			Wrapper::Expression => expression(self)?,
+			Wrapper::Return => {
+				self.writer.write_str("return ")?;
+				expression(self)?;
+				self.cast(annotation)?;
+			}
+			Wrapper::Local(name) => {
+				let name = self.name(name)?;
+
+				self.comment("type", annotation)?;
+				write!(self.writer, "local {name}")?;
+				self.colon(annotation)?;
+				self.writer.write_str(assignment)?;
+				expression(self)?;
+				write!(self.writer, "{gap}{newline}return {name}")?;
+			}
+			Wrapper::Global(name) => {
+				let name = self.name(name)?;
+
+				self.comment("type", annotation)?;
+
+				match annotation {
+					// Teal globals have to be declared
+					Some((Dialect::Teal, ty)) => {
+						write!(self.writer, "global {name}: {ty}{assignment}")?
+					}
+					_ => write!(self.writer, "{name}{assignment}")?,
+				}
+
+				expression(self)?;
+				self.cast(annotation)?;
+			}
+			Wrapper::Factory => {
+				for _ in 0..options.indent_size() {
+					self.indent.push(options.indent_char);
+				}
+
+				self.comment("return", annotation)?;
+				self.writer.write_str("return function()")?;
+				self.colon(annotation)?;
+
+				write!(self.writer, "{gap}{}return ", self.indent)?;
+				expression(self)?;
+				write!(self.writer, "{gap}end")?;
+
+				self.indent.clear();
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Write Luau `:: T` type assertion
+	fn cast(&mut self, annotation: Option<(Dialect, &str)>) -> Result<()> {
+		if let Some((Dialect::Luau, ty)) = annotation {
+			write!(self.writer, " :: {ty}")?;
+		}
+
+		Ok(())
+	}
+
+	/// Write `: T` type of a variable or return value
+	fn colon(&mut self, annotation: Option<(Dialect, &str)>) -> Result<()> {
+		if let Some((Dialect::Luau | Dialect::Teal, ty)) = annotation {
+			write!(self.writer, ": {ty}")?;
+		}
+
+		Ok(())
+	}
+
+	/// Write LuaLS `---@tag T` comment on its own line
+	fn comment(&mut self, tag: &str, annotation: Option<(Dialect, &str)>) -> Result<()> {
+		if let Some((Dialect::LuaLS, ty)) = annotation {
+			write!(
+				self.writer,
+				"---@{tag} {ty}{}",
+				self.options.newline.as_str()
+			)?;
+		}
+
+		Ok(())
+	}
+
+	fn name<'n>(&self, name: &'n str) -> Result<&'n str> {
+		if self.options.target.is_identifier(name) {
+			Ok(name)
+		} else {
+			Err(Error::InvalidName(name.to_owned()))
+		}
+	}
+
+	pub(crate) fn expression(&mut self, value: &Value) -> Result<()> {
+		let mut stack = Vec::new();
+
+		let result = self
+			.value(value, &mut stack)
+			.and_then(|_| self.entries(&mut stack));
+
+		result.map_err(|err| err.within(stack.iter().map(Frame::segment)))
+	}
+
+	/// Write scalar or open table and push its frame
+	fn value<'v>(&mut self, value: &'v Value, stack: &mut Vec<Frame<'v>>) -> Result<()> {
+		let options = self.options;
+
+		let entries = match value {
+			Value::String(s) => return self.string(s),
+			Value::Number(n) => return self.number(n),
+			Value::Bool(b) => return self.boolean(*b),
+			Value::Null => return self.null(),
+			Value::Array(values) => {
+				if options.null_policy == NullPolicy::Indexed && values.iter().any(Value::is_null) {
+					let length = options.length_field.then_some(values.len());
+					Entries::Indexed(values.iter().enumerate(), length)
+				} else {
+					Entries::Positional(values.iter())
+				}
+			}
+			Value::Object(map) if options.key_order == KeyOrder::Insertion => {
+				Entries::Object(map.iter())
+			}
+			Value::Object(map) => {
+				let mut entries: Vec<_> = map.iter().collect();
+				entries.sort_by(|(a, _), (b, _)| options.key_order.compare(a, b));
+
+				Entries::Sorted(entries.into_iter())
+			}
+		};
+
+		if stack.len() >= options.max_depth {
+			return Err(Error::TooDeep {
+				pointer: String::new(),
+			});
+		}
+
+		let flattened = !self.flat && self.fits(value);
+		self.flat |= flattened;
+
+		let broken = !self.flat;
+		let outer = self.open_table(broken)?;
+
+		stack.push(Frame {
+			entries,
+			key: Key::Positional,
+			position: 0,
+			count: 0,
+			outer,
+			broken,
+			flattened,
+		});
+
+		Ok(())
+	}
+
+	/// Write entries of the open tables until the stack is empty
+	fn entries<'v>(&mut self, stack: &mut Vec<Frame<'v>>) -> Result<()> {
+		let options = self.options;
+
+		while let Some(frame) = stack.last_mut() {
+			let Some((key, entry)) = frame.entries.next() else {
+				if let Some(frame) = stack.pop() {
+					self.close(frame)?;
+				}
+
+				continue;
+			};
+
+			frame.key = key;
+			frame.position += 1;
+
+			if let Entry::Value(Value::Null) = entry {
+				match (&options.null_policy, key) {
+					(NullPolicy::Omit, _) | (NullPolicy::Indexed, Key::Index(_)) => continue,
+					_ => {}
+				}
+			}
+
+			self.entry(frame.count, frame.broken, key)?;
+			frame.count += 1;
+
+			match entry {
+				Entry::Value(value) => self.value(value, stack)?,
+				Entry::Length(length) => write!(self.writer, "{length}")?,
+			}
+		}
+
+		Ok(())
+	}
+
+	fn close(&mut self, frame: Frame) -> Result<()> {
+		self.close_table(frame.count, frame.outer, frame.broken)?;
+
+		if frame.flattened {
+			self.flat = false;
+		}
+
+		Ok(())
+	}
+
+	/// Write the opening brace and return the indentation length to restore on closing
+	pub(crate) fn open_table(&mut self, broken: bool) -> Result<usize> {
+		let options = self.options;
+		let outer = self.indent.len();
+
+		self.writer.write_char('{')?;
+
+		if broken {
+			for _ in 0..options.indent_size() {
+				self.indent.push(options.indent_char);
+			}
+		}
+
+		Ok(outer)
+	}
+
+	/// Write the separator after the entries written so far and the key of the next one
+	pub(crate) fn entry(&mut self, count: usize, broken: bool, key: Key) -> Result<()> {
+		let options = self.options;
+
+		if count > 0 {
+			self.writer.write_char(options.separator.as_char())?;
+
+			if !broken {
+				self.writer.write_char(' ')?;
+			}
+		}
+
+		if broken {
+			self.writer.write_str(options.line_break())?;
+			self.writer.write_str(&self.indent)?;
+		}
+
+		match key {
+			Key::Positional => {}
+			Key::Index(index) => write!(self.writer, "[{index}]{}", options.assignment())?,
+			Key::Name(key) => write_key(&mut self.writer, key, options)?,
+		}
+
+		Ok(())
+	}
+
+	pub(crate) fn close_table(&mut self, count: usize, outer: usize, broken: bool) -> Result<()> {
+		let options = self.options;
+
+		if broken {
+			if count > 0 && options.trailing_separator() {
+				self.writer.write_char(options.separator.as_char())?;
+			}
+
+			self.indent.truncate(outer);
+
+			self.writer.write_str(options.line_break())?;
+			self.writer.write_str(&self.indent)?;
+		}
+
+		Ok(self.writer.write_char('}')?)
+	}
+
+	pub(crate) fn string(&mut self, string: &str) -> Result<()> {
+		Ok(write_quoted(&mut self.writer, string, self.options)?)
+	}
+
+	pub(crate) fn number(&mut self, number: &Number) -> Result<()> {
+		Ok(self.writer.write_str(&self.options.target.number(number))?)
+	}
+
+	pub(crate) fn boolean(&mut self, boolean: bool) -> Result<()> {
+		Ok(self
+			.writer
+			.write_str(if boolean { "true" } else { "false" })?)
+	}
+
+	pub(crate) fn null(&mut self) -> Result<()> {
+		Ok(self.writer.write_str(null(self.options)?)?)
+	}
+
+	/// Whether the table written flat, followed by a separator, ends within [`Layout::Width`]
+	fn fits(&self, value: &Value) -> bool {
+		let Layout::Width(width) = self.options.layout else {
+			return false;
+		};
+
+		let Some(remaining) = width.checked_sub(self.writer.column + 1) else {
+			return false;
+		};
+
+		let mut emitter = Emitter {
+			writer: Column {
+				writer: Limit { remaining },
+				column: 0,
+			},
+			options: self.options,
+			indent: String::new(),
+			flat: true,
+		};
+
+		// Errors like disallowed nulls are reported once the table is written for real
+		emitter.expression(value).is_ok()
+	}
+}
+
+/// Table that has been opened but not closed yet
+struct Frame<'v> {
+	entries: Entries<'v>,
+	/// Key of the entry being written
+	key: Key<'v>,
+	/// Number of entries taken so far, including skipped ones
+	position: usize,
+	/// Number of entries written so far
+	count: usize,
+	/// Indentation length to restore once the table is closed
+	outer: usize,
+	broken: bool,
+	/// Whether this table switched the emitter to flat mode
+	flattened: bool,
+}
+
+impl Frame<'_> {
+	/// JSON pointer segment of the entry being written
+	fn segment(&self) -> String {
+		match self.key {
+			Key::Positional => (self.position - 1).to_string(),
+			Key::Index(index) => (index - 1).to_string(),
+			Key::Name(key) => key.to_owned(),
+		}
+	}
+}
+
+enum Entries<'v> {
+	Positional(slice::Iter<'v, Value>),
+	/// Array with explicit indices followed by the optional length field
+	Indexed(iter::Enumerate<slice::Iter<'v, Value>>, Option<usize>),
+	Object(map::Iter<'v>),
+	Sorted(vec::IntoIter<(&'v String, &'v Value)>),
+}
+
+enum Entry<'v> {
+	Value(&'v Value),
+	Length(usize),
+}
+
+impl<'v> Iterator for Entries<'v> {
+	type Item = (Key<'v>, Entry<'v>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self {
+			Entries::Positional(values) => {
+				values.next().map(|v| (Key::Positional, Entry::Value(v)))
+			}
+			Entries::Indexed(values, length) => match values.next() {
+				Some((i, v)) => Some((Key::Index(i + 1), Entry::Value(v))),
+				None => length.take().map(|n| (Key::Name("n"), Entry::Length(n))),
+			},
+			Entries::Object(map) => map.next().map(|(k, v)| (Key::Name(k), Entry::Value(v))),
+			Entries::Sorted(entries) => {
+				entries.next().map(|(k, v)| (Key::Name(k), Entry::Value(v)))
+			}
+		}
+	}
+}
+
+/// Lua expression written in place of JSON `null`
+pub(crate) fn null(options: &Options) -> Result<&str> {
+	match &options.null_policy {
+		NullPolicy::Sentinel(sentinel) => Ok(sentinel),
+		NullPolicy::Error => Err(Error::Null {
+			pointer: String::new(),
+		}),
+		_ => Ok("nil"),
+	}
+}
+
+/// Writer that keeps track of the current column
+struct Column<W> {
+	writer: W,
+	column: usize,
+}
+
+impl<W: Write> Write for Column<W> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let (line, column) = match s.rfind('\n') {
+			Some(newline) => (&s[newline + 1..], 0),
+			None => (s, self.column),
+		};
+
+		self.column = column + line.chars().map(width).sum::<usize>();
+		self.writer.write_str(s)
+	}
+}
+
+fn width(char: char) -> usize {
+	match char {
+		'\t' => TAB_WIDTH,
+		_ => 1,
+	}
+}
+
+/// Writer that only counts columns and fails once there are none left
+struct Limit {
+	remaining: usize,
+}
+
+impl Write for Limit {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let width = s.chars().map(width).sum();
+
+		self.remaining = self.remaining.checked_sub(width).ok_or(fmt::Error)?;
+
+		Ok(())
+	}
+}
+
+/// Adapter that lets the emitter write into `io::Write` while keeping the original error
+pub(crate) struct IoWriter<W> {
+	pub(crate) writer: W,
+	pub(crate) error: Option<io::Error>,
+}
+
+impl<W: io::Write> Write for IoWriter<W> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.writer.write_all(s.as_bytes()).map_err(|err| {
+			self.error = Some(err);
+			fmt::Error
+		})
+	}
+}
+
+pub(crate) fn write_key<W: Write>(writer: &mut W, key: &str, options: &Options) -> fmt::Result {
+	match options.key_style {
+		KeyStyle::Identifier if options.target.is_identifier(key) => {
+			writer.write_str(key)?;
+		}
+		_ => {
+			writer.write_char('[')?;
+			write_quoted(writer, key, options)?;
+			writer.write_char(']')?;
+		}
+	}
+
+	writer.write_str(options.assignment())
+}
+
+pub(crate) fn get_key(key: &str, options: &Options) -> String {
+	let mut lua = String::with_capacity(key.len() + 7);
+	let _ = write_key(&mut lua, key, options);
+	lua
+}
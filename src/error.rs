@@ -0,0 +1,271 @@
+use std::{
+	fmt::{self, Display},
+	io,
+};
+
+/// Errors that can occur while converting between JSON and Lua
+#[derive(Debug)]
+pub enum Error {
+	/// Input is not valid JSON
+	Syntax {
+		line: usize,
+		column: usize,
+		message: String,
+		/// Source line around the error with a `^` under the column
+		snippet: String,
+	},
+	/// Root value is not an array or object while [`Options::require_table`](crate::Options::require_table) is set
+	NotATable,
+	/// Value at the JSON pointer is `null` while [`NullPolicy::Error`](crate::NullPolicy::Error) is set
+	Null { pointer: String },
+	/// Table at the JSON pointer is nested deeper than [`Options::max_depth`](crate::Options::max_depth)
+	TooDeep { pointer: String },
+	/// JSON input is longer than [`Options::max_input_size`](crate::Options::max_input_size)
+	InputTooLarge { limit: usize },
+	/// Lua output would be longer than [`Options::max_output_size`](crate::Options::max_output_size)
+	OutputTooLarge { limit: usize },
+	/// String at the JSON pointer, or a key of the object there, is longer than
+	/// [`Options::max_string_length`](crate::Options::max_string_length)
+	StringTooLong { pointer: String, limit: usize },
+	/// Array at the JSON pointer is longer than [`Options::max_array_length`](crate::Options::max_array_length)
+	ArrayTooLong { pointer: String, limit: usize },
+	/// Object at the JSON pointer has more keys than [`Options::max_object_keys`](crate::Options::max_object_keys)
+	TooManyKeys { pointer: String, limit: usize },
+	/// Input has more values than [`Options::max_nodes`](crate::Options::max_nodes), the
+	/// JSON pointer being the first one over the limit
+	TooManyNodes { pointer: String, limit: usize },
+	/// Name given to [`Wrapper`](crate::Wrapper) is not a valid identifier for the target,
+	/// or a type name shadows a built-in type
+	InvalidName(String),
+	/// JSON Schema at the JSON pointer is invalid or not supported by
+	/// [`schema::generate`](crate::schema::generate)
+	Schema { pointer: String, message: String },
+	/// Input is not a valid Lua data expression
+	Lua {
+		line: usize,
+		column: usize,
+		message: String,
+	},
+	/// Writing the output failed
+	Io(io::Error),
+	/// Writing the output into a formatter failed
+	Fmt(fmt::Error),
+	/// Error reported by a `Serialize` or `Deserialize` implementation
+	Message(String),
+}
+
+impl Error {
+	/// JSON pointer of the value that couldn't be converted, `""` being the root
+	pub fn pointer(&self) -> Option<&str> {
+		match self {
+			Error::NotATable => Some(""),
+			Error::Null { pointer }
+			| Error::TooDeep { pointer }
+			| Error::StringTooLong { pointer, .. }
+			| Error::ArrayTooLong { pointer, .. }
+			| Error::TooManyKeys { pointer, .. }
+			| Error::TooManyNodes { pointer, .. }
+			| Error::Schema { pointer, .. } => Some(pointer),
+			_ => None,
+		}
+	}
+
+	/// Line and column of syntax errors in JSON or Lua input
+	pub fn position(&self) -> Option<(usize, usize)> {
+		match self {
+			Error::Syntax { line, column, .. } | Error::Lua { line, column, .. } => {
+				Some((*line, *column))
+			}
+			_ => None,
+		}
+	}
+
+	/// Prepend pointer segment to errors raised by a nested value
+	pub(crate) fn nested(self, segment: impl Display) -> Self {
+		self.within([segment])
+	}
+
+	/// Prepend pointer segments, outermost first, to errors raised by a nested value
+	pub(crate) fn within<S: Display>(mut self, segments: impl IntoIterator<Item = S>) -> Self {
+		if let Error::Null { pointer }
+		| Error::TooDeep { pointer }
+		| Error::StringTooLong { pointer, .. }
+		| Error::ArrayTooLong { pointer, .. }
+		| Error::TooManyKeys { pointer, .. }
+		| Error::TooManyNodes { pointer, .. } = &mut self
+		{
+			let mut prefix = String::new();
+
+			for segment in segments {
+				let segment = segment.to_string().replace('~', "~0").replace('/', "~1");
+
+				prefix.push('/');
+				prefix.push_str(&segment);
+			}
+
+			pointer.insert_str(0, &prefix);
+		}
+
+		self
+	}
+
+	pub(crate) fn syntax(err: serde_json::Error, json: &str) -> Self {
+		Self::syntax_with(err, |line, column| snippet(json, line, column))
+	}
+
+	/// Syntax error in input that wasn't kept as a whole, `text` being what is left of
+	/// the line with the error once `start` bytes were dropped from its beginning
+	pub(crate) fn syntax_in_line(err: serde_json::Error, text: &str, start: usize) -> Self {
+		Self::syntax_with(err, |_, column| {
+			snippet(text, 1, column.saturating_sub(start))
+		})
+	}
+
+	fn syntax_with(err: serde_json::Error, snippet: impl FnOnce(usize, usize) -> String) -> Self {
+		let (line, column) = (err.line(), err.column());
+
+		let message = err.to_string();
+		let message = message
+			.strip_suffix(&format!(" at line {line} column {column}"))
+			.unwrap_or(&message)
+			.to_owned();
+
+		Error::Syntax {
+			line,
+			column,
+			message,
+			snippet: snippet(line, column),
+		}
+	}
+}
+
+/// Characters shown on each side of the error in long lines
+pub(crate) const SNIPPET_RADIUS: usize = 40;
+
+fn snippet(source: &str, line: usize, column: usize) -> String {
+	let text = source
+		.lines()
+		.nth(line.saturating_sub(1))
+		.unwrap_or_default();
+	let chars: Vec<char> = text.chars().collect();
+
+	// Column counts bytes and is 0 when the error is at the start of the line
+	let byte = column.saturating_sub(1).min(text.len());
+	let column = text
+		.char_indices()
+		.take_while(|(index, _)| *index < byte)
+		.count();
+
+	let start = column.saturating_sub(SNIPPET_RADIUS);
+	let end = (column + SNIPPET_RADIUS).min(chars.len());
+
+	let mut snippet: String = chars[start..end].iter().collect();
+	snippet.push('\n');
+
+	// Keep tabs so the caret lines up with the text above
+	for char in &chars[start..column.min(end)] {
+		snippet.push(if *char == '\t' { '\t' } else { ' ' });
+	}
+
+	snippet.push('^');
+	snippet
+}
+
+/// Alias for a `Result` with the error type [`json2lua::Error`](Error)
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Syntax {
+				line,
+				column,
+				message,
+				..
+			} => write!(f, "{message} at line {line} column {column}"),
+			Error::NotATable => write!(f, "expected JSON array or object at the root"),
+			Error::Null { pointer } => write!(f, "null value at `{pointer}` is not allowed"),
+			Error::TooDeep { pointer } => {
+				write!(f, "table at `{pointer}` exceeds the maximum nesting depth")
+			}
+			Error::InputTooLarge { limit } => write!(f, "input is larger than {limit} bytes"),
+			Error::OutputTooLarge { limit } => write!(f, "output is larger than {limit} bytes"),
+			Error::StringTooLong { pointer, limit } => {
+				write!(f, "string at `{pointer}` is longer than {limit} bytes")
+			}
+			Error::ArrayTooLong { pointer, limit } => {
+				write!(f, "array at `{pointer}` has more than {limit} elements")
+			}
+			Error::TooManyKeys { pointer, limit } => {
+				write!(f, "object at `{pointer}` has more than {limit} keys")
+			}
+			Error::TooManyNodes { pointer, limit } => {
+				write!(
+					f,
+					"input has more than {limit} values, the last at `{pointer}`"
+				)
+			}
+			Error::InvalidName(name) => {
+				write!(f, "`{name}` is not a valid Lua identifier or type name")
+			}
+			Error::Schema { pointer, message } => {
+				write!(f, "invalid schema at `{pointer}`: {message}")
+			}
+			Error::Lua {
+				line,
+				column,
+				message,
+			} => write!(f, "{message} at line {line} column {column}"),
+			Error::Io(err) => err.fmt(f),
+			Error::Fmt(err) => err.fmt(f),
+			Error::Message(message) => f.write_str(message),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Io(err) => Some(err),
+			Error::Fmt(err) => Some(err),
+			Error::Syntax { .. }
+			| Error::NotATable
+			| Error::Null { .. }
+			| Error::TooDeep { .. }
+			| Error::InputTooLarge { .. }
+			| Error::OutputTooLarge { .. }
+			| Error::StringTooLong { .. }
+			| Error::ArrayTooLong { .. }
+			| Error::TooManyKeys { .. }
+			| Error::TooManyNodes { .. }
+			| Error::InvalidName(_)
+			| Error::Schema { .. }
+			| Error::Lua { .. }
+			| Error::Message(_) => None,
+		}
+	}
+}
+
+impl From<fmt::Error> for Error {
+	fn from(err: fmt::Error) -> Self {
+		Error::Fmt(err)
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Error::Io(err)
+	}
+}
+
+impl serde::ser::Error for Error {
+	fn custom<T: Display>(msg: T) -> Self {
+		Error::Message(msg.to_string())
+	}
+}
+
+impl serde::de::Error for Error {
+	fn custom<T: Display>(msg: T) -> Self {
+		Error::Message(msg.to_string())
+	}
+}
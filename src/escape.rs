@@ -0,0 +1,98 @@
+use std::fmt::{self, Write};
+
+use crate::Options;
+
+/// Turn string into a double quoted Lua string literal
+pub(crate) fn quote(string: &str, options: &Options) -> String {
+	let mut quoted = String::with_capacity(string.len() + 2);
+	// Writing into a `String` can't fail
+	let _ = write_quoted(&mut quoted, string, options);
+	quoted
+}
+
+/// Write string as a double quoted Lua string literal
+pub(crate) fn write_quoted<W: Write>(
+	writer: &mut W,
+	string: &str,
+	options: &Options,
+) -> fmt::Result {
+	writer.write_char('"')?;
+	write_escaped(writer, string, options)?;
+	writer.write_char('"')
+}
+
+fn write_escaped<W: Write>(writer: &mut W, string: &str, options: &Options) -> fmt::Result {
+	// Start of the run of characters that don't need escaping
+	let mut start = 0;
+
+	for (i, char) in string.char_indices() {
+		let escape = match char {
+			'\x07' => "\\a",
+			'\x08' => "\\b",
+			'\x0C' => "\\f",
+			'\n' => "\\n",
+			'\r' => "\\r",
+			'\t' => "\\t",
+			'\x0B' => "\\v",
+			'\\' => "\\\\",
+			'"' => "\\\"",
+			_ if char.is_control() || (options.ascii_only && !char.is_ascii()) => "",
+			_ => continue,
+		};
+
+		writer.write_str(&string[start..i])?;
+		start = i + char.len_utf8();
+
+		if !escape.is_empty() {
+			writer.write_str(escape)?;
+		} else if !char.is_ascii() && options.target.has_unicode_escapes() {
+			write!(writer, "\\u{{{:X}}}", char as u32)?;
+		} else {
+			// Always three digits so a following digit can't be read as part of the escape
+			for byte in char.encode_utf8(&mut [0; 4]).bytes() {
+				write!(writer, "\\{byte:03}")?;
+			}
+		}
+	}
+
+	writer.write_str(&string[start..])
+}
+
+#[cfg(test)]
+mod test {
+	use super::quote;
+	use crate::{LuaTarget, Options};
+
+	#[test]
+	fn control_characters() {
+		let options = Options::new();
+
+		assert_eq!(
+			quote("\x07\x08\x0C\n\r\t\x0B", &options),
+			r#""\a\b\f\n\r\t\v""#
+		);
+		assert_eq!(quote("\0\x1B[0m\x7F", &options), r#""\000\027[0m\127""#);
+		assert_eq!(quote("\x001", &options), r#""\0001""#);
+		assert_eq!(quote("\"a\\b\"", &options), r#""\"a\\b\"""#);
+		assert_eq!(quote("\u{85}", &options), r#""\u{85}""#);
+		assert_eq!(
+			quote("\u{85}", &options.target(LuaTarget::Lua51)),
+			r#""\194\133""#
+		);
+	}
+
+	#[test]
+	fn ascii_only() {
+		let options = Options::new().ascii_only(true);
+
+		assert_eq!(quote("zażółć €", &Options::new()), "\"zażółć €\"");
+		assert_eq!(
+			quote("zażółć €", &options),
+			r#""za\u{17C}\u{F3}\u{142}\u{107} \u{20AC}""#
+		);
+		assert_eq!(
+			quote("zażółć €", &options.target(LuaTarget::LuaJit)),
+			r#""za\197\188\195\179\197\130\196\135 \226\130\172""#
+		);
+	}
+}
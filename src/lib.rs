@@ -34,16 +34,34 @@
 
 #![allow(clippy::tabs_in_doc_comments)]
 
-use indexmap::IndexMap;
-use serde::Deserialize;
-use serde_json::{from_str, Result, Value};
-
-#[derive(Deserialize)]
-#[serde(untagged)]
-enum Json {
-	Sequence(Vec<Value>),
-	Map(IndexMap<String, Value>),
-}
+mod emit;
+mod error;
+mod escape;
+mod limits;
+mod lua;
+mod options;
+mod parser;
+mod stream;
+
+pub mod de;
+pub mod schema;
+pub mod ser;
+pub mod types;
+
+pub use de::from_lua_str;
+pub use error::*;
+pub use lua::*;
+pub use options::*;
+pub use ser::{to_lua_string, to_lua_vec, to_lua_writer};
+
+use emit::{Emitter, IoWriter};
+use limits::Limited;
+use parser::Parser;
+use serde_json::Value;
+use std::{
+	fmt,
+	io::{self, Read},
+};
 
 /// Parse JSON string into a Lua table
 ///
@@ -67,92 +85,186 @@ enum Json {
 /// assert_eq!(parse(json).unwrap(), lua);
 /// ```
 pub fn parse(json: &str) -> Result<String> {
-	let mut lua = String::from("{\n");
-
-	match from_str(json)? {
-		Json::Sequence(json) => {
-			for value in json {
-				lua.push_str(&walk(None, &value, 1));
-			}
-		}
-		Json::Map(json) => {
-			for (key, value) in json {
-				lua.push_str(&walk(Some(&validate_string(&key)), &value, 1));
-			}
-		}
-	}
-
-	lua.push('}');
+	parse_with(json, &Options::default())
+}
 
+/// Parse JSON string into a Lua table using custom formatting [`Options`]
+///
+/// ```rust
+/// use json2lua::{parse_with, NullPolicy, Options};
+///
+/// let json = r#"{
+/// 	"string": "abc",
+/// 	"null": null
+/// }"#;
+///
+/// let lua = r#"{
+///   ["string"] = "abc",
+/// }"#;
+///
+/// let options = Options::new()
+/// 	.indent_char(' ')
+/// 	.indent_width(2)
+/// 	.null_policy(NullPolicy::Omit);
+///
+/// assert_eq!(parse_with(json, &options).unwrap(), lua);
+/// ```
+pub fn parse_with(json: &str, options: &Options) -> Result<String> {
+	let mut lua = String::with_capacity(json.len());
+	parse_to_fmt(json, &mut lua, options)?;
 	Ok(lua)
 }
 
-fn walk(key: Option<&str>, value: &Value, depth: usize) -> String {
-	let mut lua = String::new();
-
-	lua.push_str(&get_indent(depth));
+/// Read JSON from the reader and write Lua into the writer
+///
+/// Each value is written as soon as it is parsed, so memory used only grows with nesting
+/// depth and the longest string, not with the size of the input. [`Layout::Width`],
+/// sorted [`KeyOrder`]s, [`NullPolicy::Indexed`] and [`Options::annotation`] need to see
+/// whole tables first, with them the input is read and parsed into memory instead.
+///
+/// Output written before an error is found in the input is left in the writer. The
+/// reader is buffered internally, the writer should be buffered when it is a file or
+/// socket.
+///
+/// ```rust
+/// use json2lua::{parse_to_writer, Options};
+///
+/// let json = r#"{"a": [1, 2]}"#;
+/// let mut lua = Vec::new();
+///
+/// parse_to_writer(json.as_bytes(), &mut lua, &Options::default()).unwrap();
+///
+/// assert_eq!(lua, b"{\n\t[\"a\"] = {\n\t\t1,\n\t\t2,\n\t},\n}");
+/// ```
+pub fn parse_to_writer<R: io::Read, W: io::Write>(
+	reader: R,
+	writer: W,
+	options: &Options,
+) -> Result<()> {
+	let mut writer = IoWriter {
+		writer,
+		error: None,
+	};
 
-	if let Some(key) = key {
-		lua.push_str(&format!("[\"{}\"] = ", validate_string(key)));
-	}
+	let result = match stream::is_supported(options) {
+		true => emit(&mut writer, options, |emitter| {
+			stream::from_reader(reader, emitter, options)
+		}),
+		false => {
+			// One byte past the limit is enough to tell the input is too large
+			let limit = u64::try_from(options.max_input_size).unwrap_or(u64::MAX);
 
-	match value {
-		Value::String(s) => lua.push_str(&format!("\"{}\"", &validate_string(s))),
-		Value::Number(n) => lua.push_str(&n.to_string()),
-		Value::Bool(b) => lua.push_str(&b.to_string()),
-		Value::Null => lua.push_str("nil"),
-		Value::Array(a) => {
-			lua.push_str("{\n");
+			let mut json = String::new();
+			reader
+				.take(limit.saturating_add(1))
+				.read_to_string(&mut json)?;
 
-			for v in a {
-				lua.push_str(&walk(None, v, depth + 1));
-			}
-
-			lua.push_str(&get_indent(depth));
-			lua.push('}');
+			convert(&json, &mut writer, options)
 		}
-		Value::Object(o) => {
-			lua.push_str("{\n");
+	};
 
-			for (k, v) in o {
-				lua.push_str(&walk(Some(k), v, depth + 1));
-			}
+	result.map_err(|err| match (err, writer.error.take()) {
+		(Error::Fmt(_), Some(err)) => Error::Io(err),
+		(err, _) => err,
+	})
+}
 
-			lua.push_str(&get_indent(depth));
-			lua.push('}');
-		}
+/// Parse JSON string and write Lua into the formatter or `String`
+///
+/// Like with [`parse_to_writer`], output written before an error is found is left in
+/// the writer.
+///
+/// ```rust
+/// use json2lua::{parse_to_fmt, Options};
+///
+/// let mut lua = String::from("return ");
+///
+/// parse_to_fmt("[true]", &mut lua, &Options::default()).unwrap();
+///
+/// assert_eq!(lua, "return {\n\ttrue,\n}");
+/// ```
+pub fn parse_to_fmt<W: fmt::Write>(json: &str, writer: &mut W, options: &Options) -> Result<()> {
+	match stream::is_supported(options) {
+		true => emit(writer, options, |emitter| {
+			stream::from_str(json, emitter, options)
+		}),
+		false => convert(json, writer, options),
 	}
+}
 
-	lua.push_str(",\n");
+/// Parse the whole input before writing it, for options that need to see whole tables
+fn convert<W: fmt::Write>(json: &str, writer: W, options: &Options) -> Result<()> {
+	let json = limits::from_str(json, options)?;
+
+	if options.require_table && !json.is_array() && !json.is_object() {
+		return Err(Error::NotATable);
+	}
 
-	lua
+	emit(writer, options, |emitter| emitter.chunk(&json))
 }
 
-fn get_indent(depth: usize) -> String {
-	let mut indent = String::new();
+/// Run the emitter over the writer, failing once the output reaches
+/// [`Options::max_output_size`]
+fn emit<'a, W: fmt::Write>(
+	writer: W,
+	options: &'a Options,
+	write: impl FnOnce(&mut Emitter<'a, &mut Limited<W>>) -> Result<()>,
+) -> Result<()> {
+	let mut writer = Limited {
+		writer,
+		remaining: options.max_output_size,
+		exceeded: false,
+	};
 
-	for _ in 0..depth {
-		indent.push('\t');
-	}
+	let result = write(&mut Emitter::new(&mut writer, options));
 
-	indent
+	result.map_err(|err| match err {
+		Error::Fmt(_) if writer.exceeded => Error::OutputTooLarge {
+			limit: options.max_output_size,
+		},
+		err => err,
+	})
 }
 
-fn validate_string(string: &str) -> String {
-	let mut validated = String::new();
-
-	for char in string.chars() {
-		match char {
-			'\n' => validated.push_str("\\n"),
-			'\t' => validated.push_str("\\t"),
-			'\r' => validated.push_str("\\r"),
-			'\\' => validated.push_str("\\\\"),
-			'"' => validated.push_str("\\\""),
-			_ => validated.push(char),
-		}
-	}
+/// Parse Lua table constructor (or any other literal) into a JSON value
+///
+/// Accepts everything [`parse`] emits as well as common hand-written forms: bare and
+/// `[1] =` keys, single-quoted and long bracket strings, comments, hex numbers, `;`
+/// separators and a leading `return`. Tables with keys `1..=n` become arrays, other
+/// tables (including empty ones) become objects. Tables nested deeper than 200 levels
+/// are rejected with [`Error::Lua`], like the reference Lua does.
+///
+/// ```rust
+/// use json2lua::lua_to_value;
+/// use serde_json::json;
+///
+/// let lua = r#"{
+/// 	name = 'sword', -- comment
+/// 	tags = { [[sharp]], "heavy" };
+/// 	["damage"] = 0x1F,
+/// }"#;
+///
+/// let json = json!({
+/// 	"name": "sword",
+/// 	"tags": ["sharp", "heavy"],
+/// 	"damage": 31,
+/// });
+///
+/// assert_eq!(lua_to_value(lua).unwrap(), json);
+/// ```
+pub fn lua_to_value(lua: &str) -> Result<Value> {
+	Ok(Parser::new(lua).parse()?.into_json())
+}
 
-	validated
+/// Parse Lua table constructor into a JSON string, see [`lua_to_value`]
+///
+/// ```rust
+/// use json2lua::lua_to_json;
+///
+/// assert_eq!(lua_to_json("{ a = 1, 'b' }").unwrap(), r#"{"a":1,"1":"b"}"#);
+/// ```
+pub fn lua_to_json(lua: &str) -> Result<String> {
+	serde_json::to_string(&lua_to_value(lua)?).map_err(serde::ser::Error::custom)
 }
 
 #[cfg(test)]
@@ -239,4 +351,758 @@ mod test {
 
 		assert_eq!(parse(json).unwrap(), lua);
 	}
+
+	#[test]
+	fn custom_options() {
+		use crate::{parse_with, Newline, NullPolicy, Options, Separator, TrailingComma};
+
+		let json = r#"{
+  "a": [1, null, 3],
+  "b": null,
+  "c": {
+    "d": true
+  }
+}"#;
+
+		let lua = "{\r\n    [\"a\"] = {\r\n        1;\r\n        3\r\n    };\r\n    [\"c\"] = {\r\n        [\"d\"] = true\r\n    }\r\n}";
+
+		let options = Options::new()
+			.indent_char(' ')
+			.indent_width(4)
+			.newline(Newline::CrLf)
+			.separator(Separator::Semicolon)
+			.trailing_comma(TrailingComma::Never)
+			.null_policy(NullPolicy::Omit);
+
+		assert_eq!(parse_with(json, &options).unwrap(), lua);
+	}
+
+	#[test]
+	fn identifier_keys() {
+		use crate::{parse_with, KeyStyle, LuaTarget, Options};
+
+		let json = r#"{
+  "name": "sword",
+  "max_hp": 100,
+  "2d": false,
+  "with space": 1,
+  "end": 2,
+  "goto": 3,
+  "continue": 4
+}"#;
+
+		let lua = r#"{
+	name = "sword",
+	max_hp = 100,
+	["2d"] = false,
+	["with space"] = 1,
+	["end"] = 2,
+	goto = 3,
+	["continue"] = 4,
+}"#;
+
+		let options = Options::new()
+			.key_style(KeyStyle::Identifier)
+			.target(LuaTarget::Luau);
+
+		assert_eq!(parse_with(json, &options).unwrap(), lua);
+
+		let options = options.target(LuaTarget::Lua54);
+
+		assert!(parse_with(json, &options)
+			.unwrap()
+			.contains("[\"goto\"] = 3"));
+	}
+
+	#[test]
+	fn escaped_keys() {
+		use crate::parse;
+
+		let json = r#"{
+  "a\"b": 1,
+  "a\\b": 2,
+  "a\u0000b": 3,
+  "nested": {
+    "a\"b": 1,
+    "a\\b": 2,
+    "a\u0000b": 3
+  },
+  "array": [
+    {
+      "a\"b": 1,
+      "a\\b": 2,
+      "a\u0000b": 3
+    }
+  ]
+}"#;
+
+		let lua = r#"{
+	["a\"b"] = 1,
+	["a\\b"] = 2,
+	["a\000b"] = 3,
+	["nested"] = {
+		["a\"b"] = 1,
+		["a\\b"] = 2,
+		["a\000b"] = 3,
+	},
+	["array"] = {
+		{
+			["a\"b"] = 1,
+			["a\\b"] = 2,
+			["a\000b"] = 3,
+		},
+	},
+}"#;
+
+		assert_eq!(parse(json).unwrap(), lua);
+	}
+
+	#[test]
+	fn targets() {
+		use crate::{parse_with, LuaTarget, Options};
+
+		let json =
+			r#"[1, 1.0, 1e300, -9223372036854775808, 18446744073709551615, 9007199254740993]"#;
+
+		let lua = |target| {
+			parse_with(json, &Options::new().target(target))
+				.unwrap()
+				.split_whitespace()
+				.collect::<String>()
+		};
+
+		assert_eq!(
+			lua(LuaTarget::Lua51),
+			"{1,1.0,1e+300,-9223372036854775808,18446744073709551615,9007199254740993,}"
+		);
+		assert_eq!(
+			lua(LuaTarget::Lua54),
+			"{1,1.0,1e+300,0x8000000000000000,18446744073709551615,9007199254740993,}"
+		);
+		assert_eq!(
+			lua(LuaTarget::LuaJit),
+			"{1,1.0,1e+300,-9223372036854775808LL,18446744073709551615ULL,9007199254740993LL,}"
+		);
+	}
+
+	#[test]
+	fn root_scalar() {
+		use crate::{parse, parse_with, Error, Options};
+
+		assert_eq!(parse("42").unwrap(), "42");
+		assert_eq!(parse(r#""hi""#).unwrap(), r#""hi""#);
+		assert_eq!(parse("null").unwrap(), "nil");
+
+		let options = Options::new().require_table(true);
+
+		assert!(matches!(parse_with("42", &options), Err(Error::NotATable)));
+		assert!(parse_with("[42]", &options).is_ok());
+	}
+
+	#[test]
+	fn null_policies() {
+		use crate::{parse_with, ser::Serializer, Error, NullPolicy, Options};
+		use serde::Serialize;
+
+		let json = r#"{"a": [1, null, 3, null], "b": null}"#;
+		let value: serde_json::Value = serde_json::from_str(json).unwrap();
+
+		let lua = |options: &Options| {
+			parse_with(json, options)
+				.unwrap()
+				.split_whitespace()
+				.collect::<String>()
+		};
+
+		let ser = |options: &Options| {
+			let mut ser = Serializer::with_options(Vec::new(), options);
+			value.serialize(&mut ser).unwrap();
+			String::from_utf8(ser.into_inner())
+				.unwrap()
+				.split_whitespace()
+				.collect::<String>()
+		};
+
+		let policies = [
+			(NullPolicy::Nil, r#"{["a"]={1,nil,3,nil,},["b"]=nil,}"#),
+			(NullPolicy::Omit, r#"{["a"]={1,3,},}"#),
+			(
+				NullPolicy::Sentinel(String::from("json.null")),
+				r#"{["a"]={1,json.null,3,json.null,},["b"]=json.null,}"#,
+			),
+		];
+
+		for (policy, expected) in policies {
+			let options = Options::new().null_policy(policy);
+
+			assert_eq!(lua(&options), expected);
+			assert_eq!(ser(&options), expected);
+		}
+
+		let options = Options::new().null_policy(NullPolicy::Indexed);
+
+		assert_eq!(lua(&options), r#"{["a"]={[1]=1,[3]=3,},["b"]=nil,}"#);
+		assert_eq!(ser(&options), r#"{["a"]={1,[3]=3,},["b"]=nil,}"#);
+
+		let options = options.length_field(true);
+
+		assert_eq!(
+			lua(&options),
+			r#"{["a"]={[1]=1,[3]=3,["n"]=4,},["b"]=nil,}"#
+		);
+		assert_eq!(ser(&options), r#"{["a"]={1,[3]=3,["n"]=4,},["b"]=nil,}"#);
+
+		let options = Options::new().null_policy(NullPolicy::Error);
+
+		assert!(matches!(
+			parse_with(json, &options),
+			Err(Error::Null { .. })
+		));
+		assert!(matches!(
+			[()].serialize(&mut Serializer::with_options(Vec::new(), &options)),
+			Err(Error::Null { .. })
+		));
+	}
+
+	#[test]
+	fn wrappers() {
+		use crate::{lua_to_value, parse_with, Error, LuaTarget, Options, Wrapper};
+
+		let json = r#"{"a": 1}"#;
+		let wrap = |wrapper| parse_with(json, &Options::new().wrapper(wrapper));
+
+		assert_eq!(
+			wrap(Wrapper::Return).unwrap(),
+			"return {\n\t[\"a\"] = 1,\n}"
+		);
+		assert_eq!(
+			wrap(Wrapper::Local(String::from("config"))).unwrap(),
+			"local config = {\n\t[\"a\"] = 1,\n}\n\nreturn config"
+		);
+		assert_eq!(
+			wrap(Wrapper::Global(String::from("CONFIG"))).unwrap(),
+			"CONFIG = {\n\t[\"a\"] = 1,\n}"
+		);
+		assert_eq!(
+			wrap(Wrapper::Factory).unwrap(),
+			"return function()\n\treturn {\n\t\t[\"a\"] = 1,\n\t}\nend"
+		);
+
+		assert_eq!(
+			lua_to_value(&wrap(Wrapper::Return).unwrap()).unwrap(),
+			serde_json::json!({"a": 1})
+		);
+
+		for name in ["", "1a", "a.b", "end"] {
+			assert!(matches!(
+				wrap(Wrapper::Local(String::from(name))),
+				Err(Error::InvalidName(_))
+			));
+		}
+
+		let options = Options::new()
+			.wrapper(Wrapper::Global(String::from("goto")))
+			.target(LuaTarget::Lua51);
+
+		assert!(parse_with(json, &options).is_ok());
+	}
+
+	#[test]
+	fn compact() {
+		use crate::{lua_to_value, parse_with, KeyStyle, Layout, NullPolicy, Options, Wrapper};
+
+		let json = r#"{"a": 1, "b": [2, 3], "c": {}, "d": [null, "x y"]}"#;
+
+		let options = Options::new()
+			.layout(Layout::Compact)
+			.key_style(KeyStyle::Identifier)
+			.indent_char(' ')
+			.indent_width(4);
+
+		let lua = parse_with(json, &options).unwrap();
+
+		assert_eq!(lua, r#"{a=1,b={2,3},c={},d={nil,"x y"}}"#);
+		assert_eq!(
+			lua_to_value(&lua).unwrap(),
+			lua_to_value(&parse_with(json, &Options::new()).unwrap()).unwrap()
+		);
+
+		let options = Options::new()
+			.layout(Layout::Compact)
+			.null_policy(NullPolicy::Indexed)
+			.wrapper(Wrapper::Local(String::from("t")));
+
+		assert_eq!(
+			parse_with(r#"[1, null, 3]"#, &options).unwrap(),
+			"local t={[1]=1,[3]=3} return t"
+		);
+
+		let options = options.wrapper(Wrapper::Factory);
+
+		assert_eq!(
+			parse_with("[[]]", &options).unwrap(),
+			"return function() return {{}} end"
+		);
+	}
+
+	#[test]
+	fn max_width() {
+		use crate::{parse_with, KeyStyle, Layout, NullPolicy, Options, Separator, Wrapper};
+
+		let json = r#"{"points": [[1, 2], [3, 4]], "name": "a long enough name", "empty": []}"#;
+
+		let options = Options::new()
+			.layout(Layout::Width(40))
+			.key_style(KeyStyle::Identifier);
+
+		assert_eq!(
+			parse_with(json, &options).unwrap(),
+			"{\n\tpoints = {{1, 2}, {3, 4}},\n\tname = \"a long enough name\",\n\tempty = {},\n}"
+		);
+
+		// Line is exactly as wide as allowed, including the trailing separator
+		let options = options.layout(Layout::Width(30));
+
+		assert_eq!(
+			parse_with(json, &options).unwrap().lines().nth(1),
+			Some("\tpoints = {{1, 2}, {3, 4}},")
+		);
+
+		let options = options.layout(Layout::Width(29));
+
+		assert_eq!(
+			parse_with(json, &options).unwrap(),
+			"{\n\tpoints = {\n\t\t{1, 2},\n\t\t{3, 4},\n\t},\n\tname = \"a long enough name\",\n\tempty = {},\n}"
+		);
+
+		let options = Options::new()
+			.layout(Layout::Width(80))
+			.separator(Separator::Semicolon)
+			.null_policy(NullPolicy::Indexed)
+			.wrapper(Wrapper::Return);
+
+		assert_eq!(
+			parse_with("[1, null, 3]", &options).unwrap(),
+			"return {[1] = 1; [3] = 3}"
+		);
+	}
+
+	#[test]
+	fn key_order() {
+		use crate::{parse_with, KeyOrder, Layout, Options};
+
+		let json = r#"{"b10": {"z": 1, "a": 2}, "b2": [{"y": 1, "x": 2}], "a": 0}"#;
+		let options = Options::new().layout(Layout::Compact);
+
+		let order = |key_order| parse_with(json, &options.clone().key_order(key_order)).unwrap();
+
+		assert_eq!(
+			order(KeyOrder::Insertion),
+			r#"{["b10"]={["z"]=1,["a"]=2},["b2"]={{["y"]=1,["x"]=2}},["a"]=0}"#
+		);
+		assert_eq!(
+			order(KeyOrder::Lexicographic),
+			r#"{["a"]=0,["b10"]={["a"]=2,["z"]=1},["b2"]={{["x"]=2,["y"]=1}}}"#
+		);
+		assert_eq!(
+			order(KeyOrder::Natural),
+			r#"{["a"]=0,["b2"]={{["x"]=2,["y"]=1}},["b10"]={["a"]=2,["z"]=1}}"#
+		);
+		assert_eq!(
+			order(KeyOrder::custom(|a, b| a
+				.len()
+				.cmp(&b.len())
+				.then(b.cmp(a)))),
+			r#"{["a"]=0,["b2"]={{["y"]=1,["x"]=2}},["b10"]={["z"]=1,["a"]=2}}"#
+		);
+	}
+
+	#[test]
+	fn errors() {
+		use crate::{parse, parse_with, ser::Serializer, Error, NullPolicy, Options};
+		use serde::Serialize;
+		use std::collections::BTreeMap;
+
+		let Err(Error::Syntax {
+			line,
+			column,
+			message,
+			snippet,
+		}) = parse("{\n\t\"a\": [1 2]\n}")
+		else {
+			panic!("expected syntax error");
+		};
+
+		assert_eq!((line, column), (2, 10));
+		assert_eq!(message, "expected `,` or `]`");
+		assert_eq!(snippet, "\t\"a\": [1 2]\n\t        ^");
+
+		let Err(err) = parse(&format!("[{}1 2]", "1, ".repeat(40))) else {
+			panic!("expected syntax error");
+		};
+
+		assert_eq!(err.position(), Some((1, 124)));
+
+		// Long lines are cut around the column
+		let Error::Syntax { snippet, .. } = err else {
+			unreachable!();
+		};
+
+		assert_eq!(
+			snippet,
+			format!(
+				", 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1 2]\n{}^",
+				" ".repeat(40)
+			)
+		);
+
+		let options = Options::new().null_policy(NullPolicy::Error);
+		let json = r#"{"items": [{"name": "a"}, {"a/b~": [0, null]}]}"#;
+
+		let err = parse_with(json, &options).unwrap_err();
+
+		assert_eq!(err.pointer(), Some("/items/1/a~1b~0/1"));
+		assert_eq!(
+			err.to_string(),
+			"null value at `/items/1/a~1b~0/1` is not allowed"
+		);
+
+		let value = BTreeMap::from([("items", vec![Some(1), None])]);
+		let err = value
+			.serialize(&mut Serializer::with_options(Vec::new(), &options))
+			.unwrap_err();
+
+		assert_eq!(err.pointer(), Some("/items/1"));
+	}
+
+	#[test]
+	fn deep_nesting() {
+		use crate::{parse_with, ser::Serializer, Annotation, Error, Layout, Options};
+		use serde::Serialize;
+		use serde_json::Value;
+
+		const DEPTH: usize = 100_000;
+
+		let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+		let options = Options::new().layout(Layout::Compact);
+
+		assert_eq!(
+			parse_with(&nested(DEPTH), &options).unwrap().len(),
+			DEPTH * 2
+		);
+
+		let err = parse_with(&nested(DEPTH), &options.clone().max_depth(DEPTH - 1)).unwrap_err();
+
+		assert!(matches!(err, Error::TooDeep { .. }), "{err}");
+		assert_eq!(err.pointer().map(str::len), Some((DEPTH - 1) * 2));
+
+		// Types are inferred recursively, so annotations keep a lower limit
+		let options = options.annotation(Annotation::Luau(String::from("Root")));
+		let err = parse_with(&nested(200), &options).unwrap_err();
+
+		assert!(matches!(err, Error::TooDeep { .. }), "{err}");
+		assert_eq!(err.pointer(), Some("/0".repeat(128).as_str()));
+
+		let json = r#"{"a": [[1]], "b": 2}"#;
+		let options = Options::new().max_depth(2);
+
+		assert!(matches!(
+			parse_with(json, &options),
+			Err(Error::TooDeep { pointer }) if pointer == "/a/0"
+		));
+		assert!(parse_with(json, &options.clone().max_depth(3)).is_ok());
+
+		let value: Value = serde_json::from_str(json).unwrap();
+		let err = value
+			.serialize(&mut Serializer::with_options(Vec::new(), &options))
+			.unwrap_err();
+
+		assert_eq!(err.pointer(), Some("/a/0"));
+	}
+
+	#[test]
+	fn limits() {
+		use crate::{parse_to_writer, parse_with, Error, Options};
+
+		let options = Options::new().max_input_size(4);
+
+		assert!(matches!(
+			parse_with("[1,2]", &options),
+			Err(Error::InputTooLarge { limit: 4 })
+		));
+		assert!(matches!(
+			parse_to_writer("[1,2]".as_bytes(), Vec::new(), &options),
+			Err(Error::InputTooLarge { limit: 4 })
+		));
+		assert!(parse_to_writer("[12]".as_bytes(), Vec::new(), &options).is_ok());
+
+		// "{\n\t1,\n\t2,\n\t3,\n}" is 15 bytes
+		let mut lua = Vec::new();
+		let options = Options::new().max_output_size(14);
+
+		assert!(matches!(
+			parse_to_writer("[1,2,3]".as_bytes(), &mut lua, &options),
+			Err(Error::OutputTooLarge { limit: 14 })
+		));
+		assert!(lua.len() <= 14);
+		assert!(parse_with("[1,2,3]", &options.max_output_size(15)).is_ok());
+
+		let limit = |options: &Options, json: &str| {
+			let err = parse_with(json, options).unwrap_err();
+			(err.pointer().map(str::to_owned), err)
+		};
+
+		let options = Options::new().max_string_length(2);
+
+		assert!(matches!(
+			limit(&options, r#"{"a": ["xx", "xxx"]}"#),
+			(Some(pointer), Error::StringTooLong { limit: 2, .. }) if pointer == "/a/1"
+		));
+		assert!(matches!(
+			limit(&options, r#"{"a": {"xxx": 1}}"#),
+			(Some(pointer), Error::StringTooLong { .. }) if pointer == "/a"
+		));
+
+		let options = Options::new().max_array_length(2);
+
+		assert!(matches!(
+			limit(&options, r#"{"a": [[1, 2], [1, 2, 3]]}"#),
+			(Some(pointer), Error::ArrayTooLong { limit: 2, .. }) if pointer == "/a/1"
+		));
+
+		// Elements past the limit are not read at all
+		let options = options.max_nodes(4);
+
+		assert!(matches!(
+			limit(&options, "[1, 2, [3, 4]]"),
+			(Some(pointer), Error::ArrayTooLong { limit: 2, .. }) if pointer.is_empty()
+		));
+
+		let options = Options::new().max_object_keys(1);
+
+		assert!(matches!(
+			limit(&options, r#"{"a": {"x": 1, "y": 2}}"#),
+			(Some(pointer), Error::TooManyKeys { limit: 1, .. }) if pointer == "/a"
+		));
+
+		let options = Options::new().max_nodes(4);
+
+		assert!(matches!(
+			limit(&options, "[1, [2, 3]]"),
+			(Some(pointer), Error::TooManyNodes { limit: 4, .. }) if pointer == "/1/1"
+		));
+		assert!(parse_with("[1, [2]]", &options).is_ok());
+		assert!(matches!(
+			parse_with("[1, [2]", &options),
+			Err(Error::Syntax { .. })
+		));
+	}
+
+	#[test]
+	fn streaming() {
+		use crate::{
+			parse_to_writer, parse_with, Annotation, Error, KeyStyle, Layout, NullPolicy, Options,
+			Wrapper,
+		};
+		use std::io::{self, Read};
+
+		let json = r#"{"a": [1, null, {"b": null}], "c d": "\u00e9", "e": [], "f": {}, "g": 1.5}"#;
+
+		let stream = |options: &Options| {
+			let mut lua = Vec::new();
+			parse_to_writer(json.as_bytes(), &mut lua, options).unwrap();
+			String::from_utf8(lua).unwrap()
+		};
+
+		for options in [
+			Options::new(),
+			Options::new().layout(Layout::Compact),
+			Options::new().null_policy(NullPolicy::Omit),
+			Options::new().null_policy(NullPolicy::Sentinel(String::from("NULL"))),
+			Options::new().key_style(KeyStyle::Identifier),
+			Options::new().wrapper(Wrapper::Factory),
+			Options::new().wrapper(Wrapper::Local(String::from("data"))),
+			// Need whole tables, so they are not streamed
+			Options::new().layout(Layout::Width(20)),
+			Options::new().null_policy(NullPolicy::Indexed),
+			Options::new().annotation(Annotation::Luau(String::from("Data"))),
+		] {
+			assert_eq!(stream(&options), parse_with(json, &options).unwrap());
+		}
+
+		let syntax = |json: &str| {
+			let err = parse_to_writer(json.as_bytes(), io::sink(), &Options::new()).unwrap_err();
+			let Error::Syntax { snippet, .. } = &err else {
+				panic!("expected syntax error, got {err}");
+			};
+
+			(err.position(), snippet.clone())
+		};
+
+		let json = "{\n\t\"a\": [1 2]\n}";
+		assert_eq!(
+			syntax(json),
+			(Some((2, 10)), String::from("\t\"a\": [1 2]\n\t        ^"))
+		);
+
+		// Only the end of long lines is kept, which is all snippets show
+		let json = format!("[{}1 2, {}]", "1, ".repeat(400), "1, ".repeat(400));
+		let err = parse_with(&json, &Options::new()).unwrap_err();
+		let Error::Syntax { snippet, .. } = err else {
+			unreachable!();
+		};
+
+		assert_eq!(syntax(&json), (Some((1, 1204)), snippet));
+
+		// Values are written before the rest of the input is read
+		struct Broken<'a>(&'a [u8]);
+
+		impl Read for Broken<'_> {
+			fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+				match self.0.read(buf)? {
+					0 => Err(io::Error::other("connection reset")),
+					read => Ok(read),
+				}
+			}
+		}
+
+		let mut lua = Vec::new();
+		let err = parse_to_writer(Broken(b"[1, [true"), &mut lua, &Options::new()).unwrap_err();
+
+		assert!(matches!(err, Error::Io(_)), "{err}");
+		assert_eq!(lua, b"{\n\t1,\n\t{\n\t\ttrue");
+	}
+
+	#[test]
+	fn luau_types() {
+		use crate::{parse_with, Annotation, KeyStyle, Layout, Options, Wrapper};
+
+		let json = r#"{"name": "sword", "tags": ["sharp"]}"#;
+
+		let options = Options::new()
+			.key_style(KeyStyle::Identifier)
+			.annotation(Annotation::Luau(String::from("Item")));
+
+		assert_eq!(
+			parse_with(json, &options).unwrap(),
+			"export type Item = {\n\tname: string,\n\ttags: {string},\n}\n\nreturn {\n\tname = \"sword\",\n\ttags = {\n\t\t\"sharp\",\n\t},\n} :: Item"
+		);
+
+		let options = options.layout(Layout::Compact);
+		let typed = |wrapper| parse_with(json, &options.clone().wrapper(wrapper)).unwrap();
+
+		assert_eq!(
+			typed(Wrapper::Local(String::from("item"))),
+			r#"export type Item = {name: string,tags: {string}} local item: Item={name="sword",tags={"sharp"}} return item"#
+		);
+		assert_eq!(
+			typed(Wrapper::Global(String::from("ITEM"))),
+			r#"export type Item = {name: string,tags: {string}} ITEM={name="sword",tags={"sharp"}} :: Item"#
+		);
+		assert_eq!(
+			typed(Wrapper::Factory),
+			r#"export type Item = {name: string,tags: {string}} return function(): Item return {name="sword",tags={"sharp"}} end"#
+		);
+
+		for name in ["my item", "string", "any"] {
+			let options = Options::new().annotation(Annotation::Luau(String::from(name)));
+
+			assert!(matches!(
+				parse_with(json, &options),
+				Err(crate::Error::InvalidName(_))
+			));
+		}
+	}
+
+	#[test]
+	fn luals_types() {
+		use crate::{parse_with, Annotation, KeyStyle, Layout, Options, Wrapper};
+
+		let json = r#"{"name": "sword", "stats": {"damage": 7, "tags": ["sharp"]}}"#;
+
+		let options = Options::new()
+			.key_style(KeyStyle::Identifier)
+			.annotation(Annotation::LuaLS(String::from("Item")));
+
+		let lua = r#"---@class ItemStats
+---@field damage integer
+---@field tags string[]
+
+---@class Item
+---@field name string
+---@field stats ItemStats
+
+---@type Item
+local data = {
+	name = "sword",
+	stats = {
+		damage = 7,
+		tags = {
+			"sharp",
+		},
+	},
+}
+
+return data"#;
+
+		assert_eq!(parse_with(json, &options).unwrap(), lua);
+
+		let options = options.layout(Layout::Compact);
+		let typed = |wrapper| parse_with(json, &options.clone().wrapper(wrapper)).unwrap();
+		let declarations = "---@class ItemStats\n---@field damage integer\n---@field tags string[]\n---@class Item\n---@field name string\n---@field stats ItemStats\n";
+
+		let table = r#"{name="sword",stats={damage=7,tags={"sharp"}}}"#;
+
+		assert_eq!(
+			typed(Wrapper::Global(String::from("ITEM"))),
+			format!("{declarations}---@type Item\nITEM={table}")
+		);
+		assert_eq!(
+			typed(Wrapper::Factory),
+			format!("{declarations}---@return Item\nreturn function() return {table} end")
+		);
+	}
+
+	#[test]
+	fn teal_types() {
+		use crate::{parse_with, Annotation, KeyStyle, Layout, Options, Wrapper};
+
+		let json = r#"[{"id": 1, "drops": [{"chance": 0.5}]}, {"id": 2, "drops": []}]"#;
+
+		let options = Options::new()
+			.key_style(KeyStyle::Identifier)
+			.layout(Layout::Width(80))
+			.annotation(Annotation::Teal(String::from("Items")));
+
+		let teal = r#"local record ItemsElementDrops
+	chance: number
+end
+
+local record ItemsElement
+	id: integer
+	drops: {ItemsElementDrops}
+end
+
+local type Items = {ItemsElement}
+
+local data: Items = {{id = 1, drops = {{chance = 0.5}}}, {id = 2, drops = {}}}
+
+return data"#;
+
+		assert_eq!(parse_with(json, &options).unwrap(), teal);
+
+		let options = options.layout(Layout::Compact);
+		let typed = |wrapper| parse_with(json, &options.clone().wrapper(wrapper)).unwrap();
+		let declarations = "local record ItemsElementDrops chance: number end local record ItemsElement id: integer drops: {ItemsElementDrops} end local type Items = {ItemsElement} ";
+		let table = r#"{{id=1,drops={{chance=0.5}}},{id=2,drops={}}}"#;
+
+		assert_eq!(
+			typed(Wrapper::Global(String::from("ITEMS"))),
+			format!("{declarations}global ITEMS: Items={table}")
+		);
+		assert_eq!(
+			typed(Wrapper::Factory),
+			format!("{declarations}return function(): Items return {table} end")
+		);
+	}
 }
@@ -0,0 +1,333 @@
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+use std::{
+	cell::{Cell, RefCell},
+	fmt,
+	ops::Deref,
+};
+
+use crate::{Annotation, Error, Options, Result};
+
+/// Deepest tables type annotations are inferred for, as types are walked recursively
+pub(crate) const ANNOTATED_DEPTH: usize = 128;
+
+/// Parse JSON into a value while enforcing the limits set in [`Options`]
+///
+/// Limits are checked while the value is being built, so oversized input fails before
+/// it is held in memory as a whole. serde_json's own recursion limit is disabled and the
+/// stack grows on the heap instead, so only [`Options::max_depth`] bounds nesting.
+pub(crate) fn from_str(json: &str, options: &Options) -> Result<Tree> {
+	if json.len() > options.max_input_size {
+		return Err(Error::InputTooLarge {
+			limit: options.max_input_size,
+		});
+	}
+
+	let state = State::new(options);
+
+	let mut deserializer = serde_json::Deserializer::from_str(json);
+	deserializer.disable_recursion_limit();
+
+	let value = state
+		.deserialize(serde_stacker::Deserializer::new(&mut deserializer))
+		.map(Tree)
+		.and_then(|value| deserializer.end().map(|_| value));
+
+	value.map_err(|err| match state.error.take() {
+		Some(err) => err,
+		None => Error::syntax(err, json),
+	})
+}
+
+/// Parsed JSON that is dropped without recursing, however deeply it is nested
+pub(crate) struct Tree(Value);
+
+impl Deref for Tree {
+	type Target = Value;
+
+	fn deref(&self) -> &Value {
+		&self.0
+	}
+}
+
+impl Drop for Tree {
+	fn drop(&mut self) {
+		dispose(self.0.take());
+	}
+}
+
+/// Drop the value one table at a time
+fn dispose(value: Value) {
+	let mut stack = vec![value];
+
+	while let Some(value) = stack.pop() {
+		match value {
+			Value::Array(values) => stack.extend(values),
+			Value::Object(map) => stack.extend(map.into_iter().map(|(_, value)| value)),
+			_ => {}
+		}
+	}
+}
+
+/// Counters shared by every nested value
+pub(crate) struct State<'a> {
+	pub(crate) options: &'a Options,
+	/// [`Options::max_depth`], lowered when types are inferred
+	max_depth: usize,
+	nodes: Cell<usize>,
+	/// Tables the value being built is nested in
+	pub(crate) depth: Cell<usize>,
+	/// Limit error that aborted deserialization, serde_json can't carry it itself
+	pub(crate) error: RefCell<Option<Error>>,
+}
+
+impl<'a> State<'a> {
+	pub(crate) fn new(options: &'a Options) -> Self {
+		let max_depth = match options.annotation {
+			Annotation::None => options.max_depth,
+			_ => options.max_depth.min(ANNOTATED_DEPTH),
+		};
+
+		Self {
+			options,
+			max_depth,
+			nodes: Cell::new(0),
+			depth: Cell::new(0),
+			error: RefCell::new(None),
+		}
+	}
+
+	pub(crate) fn fail<E: de::Error>(&self, err: Error) -> E {
+		let message = err.to_string();
+		*self.error.borrow_mut() = Some(err);
+		E::custom(message)
+	}
+
+	/// Add pointer segment to the limit error once it propagates out of a nested value
+	pub(crate) fn nested<'s, E>(
+		&'s self,
+		segment: impl fmt::Display + 's,
+	) -> impl FnOnce(E) -> E + 's {
+		move |err| {
+			let mut error = self.error.borrow_mut();
+
+			if let Some(limit) = error.take() {
+				*error = Some(limit.nested(segment));
+			}
+
+			err
+		}
+	}
+
+	/// Enter a table, callers leave it once all of its values are read
+	pub(crate) fn table<E: de::Error>(&self) -> std::result::Result<(), E> {
+		let depth = self.depth.get() + 1;
+
+		if depth > self.max_depth {
+			return Err(self.fail(Error::TooDeep {
+				pointer: String::new(),
+			}));
+		}
+
+		self.depth.set(depth);
+		Ok(())
+	}
+
+	fn elements<'de, A: SeqAccess<'de>>(
+		&self,
+		seq: &mut A,
+		values: &mut Vec<Value>,
+	) -> std::result::Result<(), A::Error> {
+		loop {
+			if values.len() == self.options.max_array_length {
+				// Fails as soon as there is another element, without reading it
+				seq.next_element_seed(Overflow(self))?;
+				return Ok(());
+			}
+
+			match seq
+				.next_element_seed(self)
+				.map_err(self.nested(values.len()))?
+			{
+				Some(value) => values.push(value),
+				None => return Ok(()),
+			}
+		}
+	}
+
+	fn entries<'de, A: MapAccess<'de>>(
+		&self,
+		map: &mut A,
+		object: &mut Map<String, Value>,
+	) -> std::result::Result<(), A::Error> {
+		while let Some(key) = map.next_key::<String>()? {
+			self.string(&key)?;
+
+			if object.len() == self.options.max_object_keys {
+				return Err(self.fail(Error::TooManyKeys {
+					pointer: String::new(),
+					limit: self.options.max_object_keys,
+				}));
+			}
+
+			let value = map.next_value_seed(self).map_err(self.nested(&key))?;
+
+			// Repeated keys replace values that were already built
+			if let Some(replaced) = object.insert(key, value) {
+				dispose(replaced);
+			}
+		}
+
+		Ok(())
+	}
+
+	pub(crate) fn node<E: de::Error>(&self) -> std::result::Result<(), E> {
+		let nodes = self.nodes.get() + 1;
+
+		if nodes > self.options.max_nodes {
+			return Err(self.fail(Error::TooManyNodes {
+				pointer: String::new(),
+				limit: self.options.max_nodes,
+			}));
+		}
+
+		self.nodes.set(nodes);
+		Ok(())
+	}
+
+	pub(crate) fn string<E: de::Error>(&self, string: &str) -> std::result::Result<(), E> {
+		if string.len() > self.options.max_string_length {
+			return Err(self.fail(Error::StringTooLong {
+				pointer: String::new(),
+				limit: self.options.max_string_length,
+			}));
+		}
+
+		Ok(())
+	}
+}
+
+impl<'de> DeserializeSeed<'de> for &State<'_> {
+	type Value = Value;
+
+	fn deserialize<D: de::Deserializer<'de>>(
+		self,
+		deserializer: D,
+	) -> std::result::Result<Value, D::Error> {
+		deserializer.deserialize_any(self)
+	}
+}
+
+impl<'de> Visitor<'de> for &State<'_> {
+	type Value = Value;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("any valid JSON value")
+	}
+
+	fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<Value, E> {
+		self.node()?;
+		Ok(Value::Bool(v))
+	}
+
+	fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Value, E> {
+		self.node()?;
+		Ok(Value::Number(v.into()))
+	}
+
+	fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Value, E> {
+		self.node()?;
+		Ok(Value::Number(v.into()))
+	}
+
+	fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Value, E> {
+		self.node()?;
+		Ok(Number::from_f64(v).map_or(Value::Null, Value::Number))
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Value, E> {
+		self.node()?;
+		self.string(v)?;
+		Ok(Value::String(v.to_owned()))
+	}
+
+	fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<Value, E> {
+		self.node()?;
+		self.string(&v)?;
+		Ok(Value::String(v))
+	}
+
+	fn visit_unit<E: de::Error>(self) -> std::result::Result<Value, E> {
+		self.node()?;
+		Ok(Value::Null)
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+		self.node()?;
+		self.table()?;
+
+		let mut values = Vec::new();
+
+		if let Err(err) = self.elements(&mut seq, &mut values) {
+			// What was read so far can be nested just as deep
+			dispose(Value::Array(values));
+			return Err(err);
+		}
+
+		self.depth.set(self.depth.get() - 1);
+
+		Ok(Value::Array(values))
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Value, A::Error> {
+		self.node()?;
+		self.table()?;
+
+		let mut object = Map::new();
+
+		if let Err(err) = self.entries(&mut map, &mut object) {
+			dispose(Value::Object(object));
+			return Err(err);
+		}
+
+		self.depth.set(self.depth.get() - 1);
+
+		Ok(Value::Object(object))
+	}
+}
+
+/// Seed for the element past [`Options::max_array_length`]
+pub(crate) struct Overflow<'s, 'a>(pub(crate) &'s State<'a>);
+
+impl<'de> DeserializeSeed<'de> for Overflow<'_, '_> {
+	type Value = ();
+
+	fn deserialize<D: de::Deserializer<'de>>(self, _: D) -> std::result::Result<(), D::Error> {
+		Err(self.0.fail(Error::ArrayTooLong {
+			pointer: String::new(),
+			limit: self.0.options.max_array_length,
+		}))
+	}
+}
+
+/// Writer that fails instead of growing the output past the limit
+pub(crate) struct Limited<W> {
+	pub(crate) writer: W,
+	pub(crate) remaining: usize,
+	pub(crate) exceeded: bool,
+}
+
+impl<W: fmt::Write> fmt::Write for Limited<W> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		match self.remaining.checked_sub(s.len()) {
+			Some(remaining) => {
+				self.remaining = remaining;
+				self.writer.write_str(s)
+			}
+			None => {
+				self.exceeded = true;
+				Err(fmt::Error)
+			}
+		}
+	}
+}
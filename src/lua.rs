@@ -0,0 +1,96 @@
+use serde_json::Number;
+
+/// Lua runtime the output is meant to be loaded by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LuaTarget {
+	/// PUC Lua 5.1
+	Lua51,
+	/// PUC Lua 5.2
+	Lua52,
+	/// PUC Lua 5.3
+	Lua53,
+	/// PUC Lua 5.4
+	#[default]
+	Lua54,
+	/// LuaJIT 2.x
+	LuaJit,
+	/// Roblox Luau
+	Luau,
+}
+
+const KEYWORDS: [&str; 21] = [
+	"and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in", "local",
+	"nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+impl LuaTarget {
+	/// Check whether `word` is a reserved word in this runtime
+	///
+	/// ```rust
+	/// use json2lua::LuaTarget;
+	///
+	/// assert!(LuaTarget::Lua54.is_reserved("goto"));
+	/// assert!(!LuaTarget::Lua51.is_reserved("goto"));
+	/// assert!(LuaTarget::Luau.is_reserved("continue"));
+	/// ```
+	pub fn is_reserved(&self, word: &str) -> bool {
+		if KEYWORDS.contains(&word) {
+			return true;
+		}
+
+		match self {
+			LuaTarget::Lua51 => false,
+			LuaTarget::Lua52 | LuaTarget::Lua53 | LuaTarget::Lua54 | LuaTarget::LuaJit => {
+				word == "goto"
+			}
+			// `continue` is only contextual in Luau but some tools still choke on it
+			LuaTarget::Luau => word == "continue",
+		}
+	}
+
+	/// Check whether `name` can be used as a bare identifier in this runtime
+	///
+	/// ```rust
+	/// use json2lua::LuaTarget;
+	///
+	/// assert!(LuaTarget::Lua54.is_identifier("max_hp"));
+	/// assert!(!LuaTarget::Lua54.is_identifier("2d"));
+	/// assert!(!LuaTarget::Lua54.is_identifier("end"));
+	/// ```
+	pub fn is_identifier(&self, name: &str) -> bool {
+		let mut chars = name.chars();
+
+		match chars.next() {
+			Some(char) if char.is_ascii_alphabetic() || char == '_' => {}
+			_ => return false,
+		}
+
+		chars.all(|char| char.is_ascii_alphanumeric() || char == '_') && !self.is_reserved(name)
+	}
+
+	/// Check whether `\u{XXXX}` escapes are understood by this runtime
+	pub(crate) fn has_unicode_escapes(&self) -> bool {
+		matches!(self, LuaTarget::Lua53 | LuaTarget::Lua54 | LuaTarget::Luau)
+	}
+
+	/// Format JSON number as a Lua numeral that keeps its value in this runtime
+	pub(crate) fn number(&self, number: &Number) -> String {
+		// Largest integer every double can hold exactly
+		const MAX_SAFE: u64 = 1 << 53;
+
+		match self {
+			// Integer literals that don't fit are read as floats, except the minimum
+			// which can only be written as a wrapping hex literal
+			LuaTarget::Lua53 | LuaTarget::Lua54 if number.as_i64() == Some(i64::MIN) => {
+				String::from("0x8000000000000000")
+			}
+			// Keep 64-bit precision with boxed cdata integers
+			LuaTarget::LuaJit => match (number.as_i64(), number.as_u64()) {
+				(Some(n), _) if n.unsigned_abs() > MAX_SAFE => format!("{n}LL"),
+				(None, Some(n)) => format!("{n}ULL"),
+				_ => number.to_string(),
+			},
+			_ => number.to_string(),
+		}
+	}
+}
@@ -0,0 +1,685 @@
+use json2lua::{
+	parse_to_writer, schema,
+	types::{Dialect, Inferrer},
+	Annotation, Error, KeyOrder, KeyStyle, Layout, LuaTarget, Newline, NullPolicy, Options,
+	Separator, TrailingComma, Wrapper,
+};
+use std::{
+	collections::HashMap,
+	env, fs,
+	io::{self, BufReader, BufWriter, Write},
+	path::{Path, PathBuf},
+	process::ExitCode,
+};
+
+const HELP: &str = "\
+Convert JSON to Lua table
+
+Usage: json2lua [OPTIONS] [INPUT]...
+
+Reads JSON from every INPUT file or directory (recursively, `.json` files only),
+or from stdin when there is no INPUT or it is `-`.
+
+Output:
+  -o, --output <FILE>        Write to FILE instead of stdout
+  -d, --output-dir <DIR>     Write every input to DIR as `.lua`, mirroring directories
+
+Formatting:
+      --compact              Write everything on a single line without whitespace
+      --max-width <N>        Keep tables that fit within N columns on one line
+      --indent-char <CHAR>   `tab`, `space` or any single character [default: tab]
+      --indent-width <N>     Indent characters per nesting level [default: 1]
+      --newline <STYLE>      `lf` or `crlf` [default: lf]
+      --separator <SEP>      `comma` or `semicolon` [default: comma]
+      --trailing-comma <W>   `always` or `never` [default: always]
+      --key-style <STYLE>    `bracketed` or `identifier` [default: bracketed]
+      --sort-keys <ORDER>    `insertion`, `lexicographic` or `natural` [default: insertion]
+      --target <LUA>         `lua51`, `lua52`, `lua53`, `lua54`, `luajit` or `luau` [default: lua54]
+      --null <POLICY>        `nil`, `omit`, `indexed` or `error` [default: nil]
+      --null-sentinel <EXPR> Write EXPR in place of nulls
+      --length-field         Add `n = length` to arrays indexed around nulls
+      --ascii-only           Escape every non-ASCII character
+      --require-table        Fail when the root is not an array or object
+
+Limits:
+      --max-depth <N>        Fail when tables are nested deeper than N
+      --max-input-size <N>   Fail when the input is larger than N bytes
+      --max-output-size <N>  Fail when the output would be larger than N bytes
+      --max-string-length <N>
+                             Fail when a string or key is longer than N bytes
+      --max-array-length <N> Fail when an array has more than N elements
+      --max-keys <N>         Fail when an object has more than N keys
+      --max-nodes <N>        Fail when the input has more than N values
+
+Module:
+      --return               Write `return {...}`
+      --local <NAME>         Write `local NAME = {...}` followed by `return NAME`
+      --global <NAME>        Write `NAME = {...}`
+      --factory              Write `return function() return {...} end`
+
+Types:
+      --luau-type <NAME>     Write inferred `export type NAME` and type the table with it
+      --luals-type <NAME>    Write inferred `---@class NAME` comments and type the table with it
+      --teal-type <NAME>     Write inferred `local record NAME` and type the table with it,
+                             files written to `--output-dir` end with `.tl`
+      --declare <SYNTAX>     Write `luau`, `teal` or `luals` declarations of a type merged
+                             from every input instead of converting them
+      --schema               Read a JSON Schema and write Luau types with a `validate` function
+      --type-name <NAME>     Name of the declared type [default: Root]
+      --literals <N>         Declare strings with at most N distinct values as literal unions
+
+  -h, --help                 Print help
+  -V, --version              Print version
+";
+
+#[derive(Debug)]
+enum Command {
+	Help,
+	Version,
+	Convert(Box<Args>),
+}
+
+#[derive(Debug)]
+struct Args {
+	inputs: Vec<PathBuf>,
+	output: Option<PathBuf>,
+	output_dir: Option<PathBuf>,
+	options: Options,
+	declare: Option<Declare>,
+	/// Type name of the JSON Schema read instead of data
+	schema: Option<String>,
+	/// Extension of files written to `output_dir`
+	extension: &'static str,
+}
+
+#[derive(Debug)]
+struct Declare {
+	dialect: Dialect,
+	name: String,
+	literals: usize,
+}
+
+fn main() -> ExitCode {
+	let args = match parse_args(env::args().skip(1)) {
+		Ok(Command::Help) => {
+			print!("{HELP}");
+			return ExitCode::SUCCESS;
+		}
+		Ok(Command::Version) => {
+			println!("json2lua {}", env!("CARGO_PKG_VERSION"));
+			return ExitCode::SUCCESS;
+		}
+		Ok(Command::Convert(args)) => args,
+		Err(err) => {
+			eprintln!("json2lua: {err}\n\nFor more information, try `--help`");
+			return ExitCode::from(2);
+		}
+	};
+
+	let generated = match (&args.declare, &args.schema) {
+		(Some(declare), _) => Some(declare_types(&args, declare)),
+		(_, Some(name)) => Some(validator(&args, name)),
+		_ => None,
+	};
+
+	if let Some(result) = generated {
+		return match result {
+			Ok(()) => ExitCode::SUCCESS,
+			Err(err) => {
+				eprintln!("json2lua: {err}");
+				ExitCode::FAILURE
+			}
+		};
+	}
+
+	let jobs = match jobs(&args) {
+		Ok(jobs) => jobs,
+		Err(err) => {
+			eprintln!("json2lua: {err}");
+			return ExitCode::FAILURE;
+		}
+	};
+
+	let mut failed = false;
+
+	for (input, output) in jobs {
+		if let Err(err) = convert(&input, output.as_deref(), &args.options) {
+			eprintln!("json2lua: {}: {err}", input.display());
+			failed = true;
+		}
+	}
+
+	if failed {
+		ExitCode::FAILURE
+	} else {
+		ExitCode::SUCCESS
+	}
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+	let mut inputs = Vec::new();
+	let mut output = None;
+	let mut output_dir = None;
+	let mut options = Options::new();
+	let mut null_policy = None;
+	let mut dialect = None;
+	let mut type_name = None;
+	let mut literals = None;
+	let mut schema = false;
+	let mut extension = "lua";
+
+	while let Some(arg) = args.next() {
+		let (flag, inline) = match arg.split_once('=') {
+			Some((flag, value)) if flag.starts_with("--") => {
+				(flag.to_owned(), Some(value.to_owned()))
+			}
+			_ => (arg.clone(), None),
+		};
+
+		let mut value = || {
+			inline
+				.clone()
+				.or_else(|| args.next())
+				.ok_or_else(|| format!("`{flag}` requires a value"))
+		};
+
+		options = match flag.as_str() {
+			"-h" | "--help" => return Ok(Command::Help),
+			"-V" | "--version" => return Ok(Command::Version),
+			"-o" | "--output" => {
+				output = Some(PathBuf::from(value()?));
+				options
+			}
+			"-d" | "--output-dir" => {
+				output_dir = Some(PathBuf::from(value()?));
+				options
+			}
+			"--indent-char" => options.indent_char(match value()?.as_str() {
+				"tab" => '\t',
+				"space" => ' ',
+				char => {
+					let mut chars = char.chars();
+
+					match (chars.next(), chars.next()) {
+						(Some(char), None) => char,
+						_ => return Err(format!("invalid indent character `{char}`")),
+					}
+				}
+			}),
+			"--indent-width" => options.indent_width(number(&flag, value()?)?),
+			"--newline" => options.newline(match value()?.as_str() {
+				"lf" => Newline::Lf,
+				"crlf" => Newline::CrLf,
+				newline => return Err(format!("invalid newline style `{newline}`")),
+			}),
+			"--separator" => options.separator(match value()?.as_str() {
+				"comma" | "," => Separator::Comma,
+				"semicolon" | ";" => Separator::Semicolon,
+				separator => return Err(format!("invalid separator `{separator}`")),
+			}),
+			"--trailing-comma" => options.trailing_comma(match value()?.as_str() {
+				"always" => TrailingComma::Always,
+				"never" => TrailingComma::Never,
+				trailing => return Err(format!("invalid trailing comma policy `{trailing}`")),
+			}),
+			"--key-style" => options.key_style(match value()?.as_str() {
+				"bracketed" => KeyStyle::Bracketed,
+				"identifier" => KeyStyle::Identifier,
+				style => return Err(format!("invalid key style `{style}`")),
+			}),
+			"--sort-keys" => options.key_order(match value()?.as_str() {
+				"insertion" => KeyOrder::Insertion,
+				"lexicographic" => KeyOrder::Lexicographic,
+				"natural" => KeyOrder::Natural,
+				order => return Err(format!("invalid key order `{order}`")),
+			}),
+			"--target" => options.target(match value()?.to_lowercase().as_str() {
+				"lua51" | "5.1" => LuaTarget::Lua51,
+				"lua52" | "5.2" => LuaTarget::Lua52,
+				"lua53" | "5.3" => LuaTarget::Lua53,
+				"lua54" | "5.4" => LuaTarget::Lua54,
+				"luajit" => LuaTarget::LuaJit,
+				"luau" => LuaTarget::Luau,
+				target => return Err(format!("invalid Lua target `{target}`")),
+			}),
+			"--null" => {
+				null_policy = Some(match value()?.as_str() {
+					"nil" => NullPolicy::Nil,
+					"omit" => NullPolicy::Omit,
+					"indexed" => NullPolicy::Indexed,
+					"error" => NullPolicy::Error,
+					policy => return Err(format!("invalid null policy `{policy}`")),
+				});
+				options
+			}
+			"--null-sentinel" => {
+				null_policy = Some(NullPolicy::Sentinel(value()?));
+				options
+			}
+			"--compact" => options.layout(Layout::Compact),
+			"--max-width" => options.layout(Layout::Width(number(&flag, value()?)?)),
+			"--length-field" => options.length_field(true),
+			"--ascii-only" => options.ascii_only(true),
+			"--require-table" => options.require_table(true),
+			"--max-depth" => options.max_depth(number(&flag, value()?)?),
+			"--max-input-size" => options.max_input_size(number(&flag, value()?)?),
+			"--max-output-size" => options.max_output_size(number(&flag, value()?)?),
+			"--max-string-length" => options.max_string_length(number(&flag, value()?)?),
+			"--max-array-length" => options.max_array_length(number(&flag, value()?)?),
+			"--max-keys" => options.max_object_keys(number(&flag, value()?)?),
+			"--max-nodes" => options.max_nodes(number(&flag, value()?)?),
+			"--return" => options.wrapper(Wrapper::Return),
+			"--local" => options.wrapper(Wrapper::Local(value()?)),
+			"--global" => options.wrapper(Wrapper::Global(value()?)),
+			"--factory" => options.wrapper(Wrapper::Factory),
+			"--luau-type" => options.annotation(Annotation::Luau(value()?)),
+			"--luals-type" => options.annotation(Annotation::LuaLS(value()?)),
+			"--teal-type" => {
+				extension = "tl";
+				options.annotation(Annotation::Teal(value()?))
+			}
+			"--declare" => {
+				dialect = Some(match value()?.as_str() {
+					"luau" => Dialect::Luau,
+					"teal" => Dialect::Teal,
+					"luals" => Dialect::LuaLS,
+					dialect => return Err(format!("invalid type syntax `{dialect}`")),
+				});
+				options
+			}
+			"--schema" => {
+				schema = true;
+				options
+			}
+			"--type-name" => {
+				type_name = Some(value()?);
+				options
+			}
+			"--literals" => {
+				literals = Some(number(&flag, value()?)?);
+				options
+			}
+			"-" => {
+				inputs.push(PathBuf::from("-"));
+				options
+			}
+			flag if flag.starts_with('-') => return Err(format!("unknown option `{flag}`")),
+			_ => {
+				inputs.push(PathBuf::from(arg));
+				options
+			}
+		};
+	}
+
+	if let Some(null_policy) = null_policy {
+		options = options.null_policy(null_policy);
+	}
+
+	if inputs.is_empty() {
+		inputs.push(PathBuf::from("-"));
+	}
+
+	if output.is_some() && output_dir.is_some() {
+		return Err(String::from(
+			"`--output` and `--output-dir` can't be used together",
+		));
+	}
+
+	if dialect.is_none() && literals.is_some() {
+		return Err(String::from("`--literals` requires `--declare`"));
+	}
+
+	if dialect.is_none() && !schema && type_name.is_some() {
+		return Err(String::from(
+			"`--type-name` requires `--declare` or `--schema`",
+		));
+	}
+
+	if dialect.is_some() && schema {
+		return Err(String::from(
+			"`--declare` and `--schema` can't be used together",
+		));
+	}
+
+	let name = type_name.unwrap_or_else(|| String::from("Root"));
+
+	let declare = dialect.map(|dialect| Declare {
+		dialect,
+		name: name.clone(),
+		literals: literals.unwrap_or(0),
+	});
+
+	let schema = schema.then_some(name);
+
+	if (declare.is_some() || schema.is_some()) && output_dir.is_some() {
+		return Err(String::from(
+			"`--declare` and `--schema` write a single file and can't be used with `--output-dir`",
+		));
+	}
+
+	let single = inputs.len() == 1 && !inputs[0].is_dir();
+
+	if schema.is_some() && !single {
+		return Err(String::from("`--schema` reads a single file"));
+	}
+
+	if output_dir.is_none() && declare.is_none() && !single {
+		return Err(String::from(
+			"converting multiple files requires `--output-dir`",
+		));
+	}
+
+	if output_dir.is_some() && inputs.iter().any(|input| input == Path::new("-")) {
+		return Err(String::from("stdin can't be converted into `--output-dir`"));
+	}
+
+	Ok(Command::Convert(Box::new(Args {
+		inputs,
+		output,
+		output_dir,
+		options,
+		declare,
+		schema,
+		extension,
+	})))
+}
+
+fn number(flag: &str, value: String) -> Result<usize, String> {
+	value
+		.parse()
+		.map_err(|_| format!("`{flag}` expects a number, got `{value}`"))
+}
+
+/// Pair every input file with its output path, `None` meaning stdout
+fn jobs(args: &Args) -> Result<Vec<(PathBuf, Option<PathBuf>)>, String> {
+	let Some(output_dir) = &args.output_dir else {
+		return Ok(vec![(args.inputs[0].clone(), args.output.clone())]);
+	};
+
+	let mut jobs = Vec::new();
+
+	for input in &args.inputs {
+		if input.is_dir() {
+			let mut files = Vec::new();
+			collect_json(input, &mut files);
+
+			for file in files {
+				let relative = file.strip_prefix(input).unwrap_or(&file);
+				let output = output_dir.join(relative).with_extension(args.extension);
+
+				jobs.push((file, Some(output)));
+			}
+		} else {
+			let name = input.file_name().map(Path::new).unwrap_or(input);
+			let output = output_dir.join(name).with_extension(args.extension);
+
+			jobs.push((input.clone(), Some(output)));
+		}
+	}
+
+	// Inputs with the same name in different directories would overwrite each other
+	let mut outputs = HashMap::new();
+
+	for (input, output) in &jobs {
+		if let Some(previous) = outputs.insert(output, input) {
+			return Err(format!(
+				"{} and {} would both be written to {}",
+				previous.display(),
+				input.display(),
+				output.as_deref().unwrap_or(output_dir).display()
+			));
+		}
+	}
+
+	Ok(jobs)
+}
+
+fn collect_json(dir: &Path, files: &mut Vec<PathBuf>) {
+	let Ok(entries) = fs::read_dir(dir) else {
+		// Let the conversion report the error
+		files.push(dir.to_owned());
+		return;
+	};
+
+	let mut paths: Vec<PathBuf> = entries
+		.filter_map(|entry| Some(entry.ok()?.path()))
+		.collect();
+	paths.sort();
+
+	for path in paths {
+		if path.is_dir() {
+			collect_json(&path, files);
+		} else if path
+			.extension()
+			.is_some_and(|extension| extension == "json")
+		{
+			files.push(path);
+		}
+	}
+}
+
+/// Merge types of every input into one and write its declarations
+fn declare_types(args: &Args, declare: &Declare) -> Result<(), String> {
+	let mut inferrer = Inferrer::new().literals(declare.literals);
+	let mut files = Vec::new();
+
+	for input in &args.inputs {
+		if input.is_dir() {
+			collect_json(input, &mut files);
+		} else {
+			files.push(input.clone());
+		}
+	}
+
+	for file in files {
+		let json = if file == Path::new("-") {
+			io::read_to_string(io::stdin().lock())
+		} else {
+			fs::read_to_string(&file)
+		};
+
+		let json = json.map_err(|err| format!("{}: {err}", file.display()))?;
+
+		inferrer.add_str(&json).map_err(|err| match &err {
+			Error::Syntax { snippet, .. } => format!("{}: {err}\n{snippet}", file.display()),
+			_ => format!("{}: {err}", file.display()),
+		})?;
+	}
+
+	let lua = inferrer
+		.ty()
+		.declare(&declare.name, declare.dialect, &args.options)
+		.map_err(|err| err.to_string())?;
+
+	write_generated(args.output.as_deref(), &lua)
+}
+
+/// Turn the JSON Schema input into Luau types and a validator
+fn validator(args: &Args, name: &str) -> Result<(), String> {
+	let input = &args.inputs[0];
+
+	let json = if input == Path::new("-") {
+		io::read_to_string(io::stdin().lock())
+	} else {
+		fs::read_to_string(input)
+	};
+
+	let json = json.map_err(|err| format!("{}: {err}", input.display()))?;
+
+	let lua = schema::generate(&json, name, &args.options).map_err(|err| match &err {
+		Error::Syntax { snippet, .. } => format!("{}: {err}\n{snippet}", input.display()),
+		_ => format!("{}: {err}", input.display()),
+	})?;
+
+	write_generated(args.output.as_deref(), &lua)
+}
+
+fn write_generated(output: Option<&Path>, lua: &str) -> Result<(), String> {
+	let written = match output {
+		Some(output) => fs::write(output, format!("{lua}\n")),
+		None => writeln!(io::stdout().lock(), "{lua}"),
+	};
+
+	written.map_err(|err| err.to_string())
+}
+
+fn convert(input: &Path, output: Option<&Path>, options: &Options) -> Result<(), String> {
+	let reader: Box<dyn io::Read> = if input == Path::new("-") {
+		Box::new(io::stdin().lock())
+	} else {
+		Box::new(BufReader::new(
+			fs::File::open(input).map_err(|err| err.to_string())?,
+		))
+	};
+
+	let Some(output) = output else {
+		return write_lua(reader, io::stdout().lock(), options);
+	};
+
+	if let Some(parent) = output.parent() {
+		fs::create_dir_all(parent).map_err(|err| format!("{}: {err}", parent.display()))?;
+	}
+
+	// Write next to the target and rename on success, so a failed
+	// conversion never clobbers or deletes an existing file
+	let staged = staging_path(output);
+
+	let file = fs::File::create(&staged).map_err(|err| format!("{}: {err}", staged.display()))?;
+
+	let result = write_lua(reader, file, options).and_then(|_| {
+		fs::rename(&staged, output).map_err(|err| format!("{}: {err}", output.display()))
+	});
+
+	if result.is_err() {
+		let _ = fs::remove_file(&staged);
+	}
+
+	result
+}
+
+fn write_lua(reader: impl io::Read, writer: impl Write, options: &Options) -> Result<(), String> {
+	let mut writer = BufWriter::new(writer);
+
+	parse_to_writer(reader, &mut writer, options).map_err(|err| match &err {
+		Error::Syntax { snippet, .. } => format!("{err}\n{snippet}"),
+		_ => err.to_string(),
+	})?;
+
+	// Files end with a newline like any other source file
+	writeln!(writer)
+		.and_then(|_| writer.flush())
+		.map_err(|err| err.to_string())
+}
+
+fn staging_path(output: &Path) -> PathBuf {
+	let mut name = std::ffi::OsString::from(".");
+	name.push(output.file_name().unwrap_or_default());
+	name.push(format!(".{}.tmp", std::process::id()));
+
+	output.with_file_name(name)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{convert, jobs, parse_args, Command};
+	use json2lua::{KeyStyle, NullPolicy, Options};
+	use std::fs;
+
+	fn args(args: &str) -> Result<Command, String> {
+		parse_args(args.split_whitespace().map(String::from))
+	}
+
+	#[test]
+	fn options() {
+		let Ok(Command::Convert(args)) =
+			args("in.json -o out.lua --key-style=identifier --indent-char space --null-sentinel json.null")
+		else {
+			panic!("expected conversion");
+		};
+
+		let options = Options::new()
+			.key_style(KeyStyle::Identifier)
+			.indent_char(' ')
+			.null_policy(NullPolicy::Sentinel(String::from("json.null")));
+
+		assert_eq!(args.options, options);
+		assert_eq!(args.inputs, ["in.json"].map(std::path::PathBuf::from));
+		assert_eq!(
+			args.output.as_deref(),
+			Some(std::path::Path::new("out.lua"))
+		);
+	}
+
+	#[test]
+	fn usage_errors() {
+		assert!(matches!(args(""), Ok(Command::Convert(_))));
+		assert!(matches!(args("--help"), Ok(Command::Help)));
+		assert!(args("--target").is_err());
+		assert!(args("--target lua6").is_err());
+		assert!(args("--unknown").is_err());
+		assert!(args("a.json b.json").is_err());
+		assert!(args("a.json b.json -d out -o out.lua").is_err());
+		assert!(args("a.json b.json -d out").is_ok());
+		assert!(args("a.json b.json --declare teal").is_ok());
+		assert!(args("a.json --declare teal -d out").is_err());
+		assert!(args("a.json --declare ts").is_err());
+		assert!(args("a.json --type-name Item").is_err());
+		assert!(args("a.json --schema --type-name Config").is_ok());
+		assert!(args("a.json b.json --schema").is_err());
+		assert!(args("a.json --schema --declare luau").is_err());
+	}
+
+	#[test]
+	fn failed_conversion_keeps_existing_output() {
+		let dir = std::env::temp_dir().join(format!("json2lua-cli-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+
+		let output = dir.join("existing.lua");
+		fs::write(&output, "precious").unwrap();
+
+		let missing = convert(&dir.join("missing.json"), Some(&output), &Options::new());
+		assert!(missing.is_err());
+		assert_eq!(fs::read_to_string(&output).unwrap(), "precious");
+
+		let invalid = dir.join("invalid.json");
+		fs::write(&invalid, "{").unwrap();
+
+		assert!(convert(&invalid, Some(&output), &Options::new()).is_err());
+		assert_eq!(fs::read_to_string(&output).unwrap(), "precious");
+		assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+		let valid = dir.join("valid.json");
+		fs::write(&valid, "[1]").unwrap();
+
+		convert(&valid, Some(&output), &Options::new()).unwrap();
+		assert_eq!(fs::read_to_string(&output).unwrap(), "{\n\t1,\n}\n");
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn clashing_outputs() {
+		let dir = std::env::temp_dir().join(format!("json2lua-jobs-{}", std::process::id()));
+
+		for name in ["a", "b"] {
+			fs::create_dir_all(dir.join(name)).unwrap();
+			fs::write(dir.join(name).join("x.json"), "[]").unwrap();
+		}
+
+		let run = |inputs: &str| {
+			let inputs = inputs.replace("DIR", &dir.display().to_string());
+
+			match args(&format!("{inputs} -d out")) {
+				Ok(Command::Convert(args)) => jobs(&args),
+				_ => panic!("expected conversion"),
+			}
+		};
+
+		assert!(run("DIR/a/x.json DIR/b/x.json").is_err());
+		assert!(run("DIR/a DIR/b").is_err());
+		assert!(run("DIR/a/x.json DIR/b").is_err());
+		assert_eq!(run("DIR/a/x.json DIR").unwrap().len(), 3);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
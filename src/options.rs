@@ -0,0 +1,536 @@
+use crate::LuaTarget;
+use std::{cmp::Ordering, fmt, sync::Arc};
+
+/// Line ending used between table entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Newline {
+	/// Unix style `\n`
+	#[default]
+	Lf,
+	/// Windows style `\r\n`
+	CrLf,
+}
+
+impl Newline {
+	pub(crate) fn as_str(&self) -> &'static str {
+		match self {
+			Newline::Lf => "\n",
+			Newline::CrLf => "\r\n",
+		}
+	}
+}
+
+/// Character placed between table entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Separator {
+	/// `,`
+	#[default]
+	Comma,
+	/// `;`
+	Semicolon,
+}
+
+impl Separator {
+	pub(crate) fn as_char(&self) -> char {
+		match self {
+			Separator::Comma => ',',
+			Separator::Semicolon => ';',
+		}
+	}
+}
+
+/// Whether the last entry of a table is followed by a separator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingComma {
+	/// `{ 1, 2, 3, }`
+	#[default]
+	Always,
+	/// `{ 1, 2, 3 }`
+	Never,
+}
+
+/// How object keys are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyStyle {
+	/// `["key"] = value`
+	#[default]
+	Bracketed,
+	/// `key = value` when the key is a valid identifier for the [`LuaTarget`], `["key"] = value` otherwise
+	Identifier,
+}
+
+/// What to do with JSON `null` values
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum NullPolicy {
+	/// Emit `nil`, which leaves holes in arrays and drops object keys once loaded
+	#[default]
+	Nil,
+	/// Skip the entry entirely
+	Omit,
+	/// Emit the given Lua expression instead, e.g. `json.null`
+	Sentinel(String),
+	/// Write arrays containing nulls with explicit `[index] = ` keys and skip the nulls
+	/// so every element stays at its original position
+	///
+	/// The serializer can't look ahead, so only elements after the first null get
+	/// explicit keys there, which loads into the same table.
+	Indexed,
+	/// Fail with [`Error::Null`](crate::Error::Null)
+	Error,
+}
+
+/// Order object keys are written in, applied recursively to nested objects
+///
+/// The serializer writes fields in the order they are serialized regardless.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum KeyOrder {
+	/// Order keys appear in the JSON input
+	#[default]
+	Insertion,
+	/// Byte-wise string order, `item10` before `item2`
+	Lexicographic,
+	/// Digit runs compared by value, `item2` before `item10`
+	Natural,
+	/// Order decided by the given comparator
+	Custom(KeyComparator),
+}
+
+impl KeyOrder {
+	/// Sort keys with a custom comparator
+	///
+	/// ```rust
+	/// use json2lua::{parse_with, KeyOrder, Options};
+	///
+	/// let options = Options::new().key_order(KeyOrder::custom(|a, b| b.cmp(a)));
+	///
+	/// assert_eq!(
+	/// 	parse_with(r#"{"a": 1, "b": 2}"#, &options).unwrap(),
+	/// 	"{\n\t[\"b\"] = 2,\n\t[\"a\"] = 1,\n}"
+	/// );
+	/// ```
+	pub fn custom(compare: impl Fn(&str, &str) -> Ordering + Send + Sync + 'static) -> Self {
+		KeyOrder::Custom(KeyComparator(Arc::new(compare)))
+	}
+
+	pub(crate) fn compare(&self, a: &str, b: &str) -> Ordering {
+		match self {
+			KeyOrder::Insertion => Ordering::Equal,
+			KeyOrder::Lexicographic => a.cmp(b),
+			KeyOrder::Natural => natural(a, b),
+			KeyOrder::Custom(KeyComparator(compare)) => compare(a, b),
+		}
+	}
+}
+
+/// Comparator used by [`KeyOrder::Custom`], equal only to its own clones
+#[derive(Clone)]
+pub struct KeyComparator(Arc<Compare>);
+
+type Compare = dyn Fn(&str, &str) -> Ordering + Send + Sync;
+
+impl fmt::Debug for KeyComparator {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("KeyComparator")
+	}
+}
+
+impl PartialEq for KeyComparator {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.0, &other.0)
+	}
+}
+
+impl Eq for KeyComparator {}
+
+/// Compare strings with digit runs compared by value, ties broken byte-wise
+fn natural(a: &str, b: &str) -> Ordering {
+	let (mut left, mut right) = (a.as_bytes(), b.as_bytes());
+
+	while let (Some(&l), Some(&r)) = (left.first(), right.first()) {
+		let order = if l.is_ascii_digit() && r.is_ascii_digit() {
+			let (l, rest_l) = split_digits(left);
+			let (r, rest_r) = split_digits(right);
+
+			left = rest_l;
+			right = rest_r;
+
+			// Without leading zeros the longer run is the larger number
+			let (l, r) = (trim_zeros(l), trim_zeros(r));
+			l.len().cmp(&r.len()).then_with(|| l.cmp(r))
+		} else {
+			left = &left[1..];
+			right = &right[1..];
+
+			l.cmp(&r)
+		};
+
+		if order != Ordering::Equal {
+			return order;
+		}
+	}
+
+	left.len().cmp(&right.len()).then_with(|| a.cmp(b))
+}
+
+fn split_digits(bytes: &[u8]) -> (&[u8], &[u8]) {
+	let end = bytes
+		.iter()
+		.position(|byte| !byte.is_ascii_digit())
+		.unwrap_or(bytes.len());
+
+	bytes.split_at(end)
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+	let start = digits
+		.iter()
+		.position(|&digit| digit != b'0')
+		.unwrap_or(digits.len());
+
+	&digits[start..]
+}
+
+/// How tables are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+	/// Every entry on its own indented line
+	#[default]
+	Expanded,
+	/// Everything on a single line without whitespace or trailing separators,
+	/// `{["a"]=1,b={2,3}}`
+	Compact,
+	/// Tables that fit within the given line width stay on one line, `{1, 2, 3}`, and
+	/// only the ones that don't are expanded, tabs count as 4 columns
+	///
+	/// The serializer can't look ahead, so it writes every table expanded instead.
+	Width(usize),
+}
+
+/// Lua chunk the table is wrapped in
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Wrapper {
+	/// Bare table constructor `{...}`
+	#[default]
+	Expression,
+	/// Module returning the table, `return {...}`
+	Return,
+	/// Module returning a named local, `local NAME = {...} return NAME`
+	Local(String),
+	/// Global assignment `NAME = {...}`
+	Global(String),
+	/// Module returning a function that builds a fresh table on every call,
+	/// `return function() return {...} end`
+	Factory,
+}
+
+/// Type definitions written before the table
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Annotation {
+	/// Plain data without types
+	#[default]
+	None,
+	/// Luau `export type NAME = {...}` inferred from the data, with the table typed as
+	/// `NAME`, e.g. `return {...} :: NAME`
+	///
+	/// A bare [`Wrapper::Expression`] becomes [`Wrapper::Return`] as the output is a chunk.
+	Luau(String),
+	/// lua-language-server `---@class` and `---@field` comments inferred from the data,
+	/// with the table declared as `---@type NAME`
+	///
+	/// Nested classes are named after their key path, see
+	/// [`Type::declare`](crate::types::Type::declare). A bare [`Wrapper::Expression`] or
+	/// [`Wrapper::Return`] becomes `local data = {...}` followed by `return data` as only
+	/// variables can be typed.
+	LuaLS(String),
+	/// Teal `record` declarations inferred from the data, with the table declared as
+	/// `local data: NAME = {...}` followed by `return data`
+	///
+	/// Nested records are named after their key path, see
+	/// [`Type::declare`](crate::types::Type::declare). A bare [`Wrapper::Expression`] or
+	/// [`Wrapper::Return`] becomes `local data` like with [`Annotation::LuaLS`], and
+	/// [`Wrapper::Global`] is declared as `global NAME: T`.
+	Teal(String),
+}
+
+/// Formatting options used by [`parse_with`](crate::parse_with)
+///
+/// ```rust
+/// use json2lua::{parse_with, Options, Separator, TrailingComma};
+///
+/// let options = Options::new()
+/// 	.indent_char(' ')
+/// 	.indent_width(2)
+/// 	.separator(Separator::Semicolon)
+/// 	.trailing_comma(TrailingComma::Never);
+///
+/// let lua = parse_with(r#"{"a": 1, "b": 2}"#, &options).unwrap();
+///
+/// assert_eq!(lua, "{\n  [\"a\"] = 1;\n  [\"b\"] = 2\n}");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+	pub(crate) indent_char: char,
+	pub(crate) indent_width: usize,
+	pub(crate) newline: Newline,
+	pub(crate) separator: Separator,
+	pub(crate) trailing_comma: TrailingComma,
+	pub(crate) key_style: KeyStyle,
+	pub(crate) target: LuaTarget,
+	pub(crate) null_policy: NullPolicy,
+	pub(crate) ascii_only: bool,
+	pub(crate) require_table: bool,
+	pub(crate) length_field: bool,
+	pub(crate) wrapper: Wrapper,
+	pub(crate) layout: Layout,
+	pub(crate) key_order: KeyOrder,
+	pub(crate) max_depth: usize,
+	pub(crate) max_input_size: usize,
+	pub(crate) max_output_size: usize,
+	pub(crate) max_string_length: usize,
+	pub(crate) max_array_length: usize,
+	pub(crate) max_object_keys: usize,
+	pub(crate) max_nodes: usize,
+	pub(crate) annotation: Annotation,
+}
+
+impl Options {
+	/// Create options matching the output of [`parse`](crate::parse)
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Character repeated to indent nested entries, `\t` by default
+	pub fn indent_char(mut self, indent_char: char) -> Self {
+		self.indent_char = indent_char;
+		self
+	}
+
+	/// Number of indent characters per nesting level, `1` by default
+	pub fn indent_width(mut self, indent_width: usize) -> Self {
+		self.indent_width = indent_width;
+		self
+	}
+
+	/// Line ending placed after every entry
+	pub fn newline(mut self, newline: Newline) -> Self {
+		self.newline = newline;
+		self
+	}
+
+	/// Character placed between table entries
+	pub fn separator(mut self, separator: Separator) -> Self {
+		self.separator = separator;
+		self
+	}
+
+	/// Whether the last entry of a table gets a separator too
+	pub fn trailing_comma(mut self, trailing_comma: TrailingComma) -> Self {
+		self.trailing_comma = trailing_comma;
+		self
+	}
+
+	/// How object keys are written
+	pub fn key_style(mut self, key_style: KeyStyle) -> Self {
+		self.key_style = key_style;
+		self
+	}
+
+	/// Lua runtime the output has to be valid for
+	pub fn target(mut self, target: LuaTarget) -> Self {
+		self.target = target;
+		self
+	}
+
+	/// What to do with JSON `null` values
+	pub fn null_policy(mut self, null_policy: NullPolicy) -> Self {
+		self.null_policy = null_policy;
+		self
+	}
+
+	/// Escape every non-ASCII character in strings and keys as `\ddd` bytes
+	pub fn ascii_only(mut self, ascii_only: bool) -> Self {
+		self.ascii_only = ascii_only;
+		self
+	}
+
+	/// Add `n = length` to arrays written with [`NullPolicy::Indexed`], like `table.pack` does,
+	/// so trailing nulls are not lost
+	///
+	/// Arrays of nothing but nulls become a lone `n` field, which
+	/// [`lua_to_value`](crate::lua_to_value) reads back as a record since `{ n = 2 }` is far
+	/// more often meant as one. Those arrays don't round-trip.
+	pub fn length_field(mut self, length_field: bool) -> Self {
+		self.length_field = length_field;
+		self
+	}
+
+	/// How tables are laid out, [`Layout::Compact`] ignores indentation, newline and
+	/// trailing separator options
+	pub fn layout(mut self, layout: Layout) -> Self {
+		self.layout = layout;
+		self
+	}
+
+	/// Order object keys are written in, insertion order by default
+	pub fn key_order(mut self, key_order: KeyOrder) -> Self {
+		self.key_order = key_order;
+		self
+	}
+
+	/// Fail with [`Error::TooDeep`](crate::Error::TooDeep) when tables are nested deeper
+	/// than this, unlimited by default
+	///
+	/// Parsing and writing grow the stack on the heap as needed, so any depth converts
+	/// without overflowing it. Type inference for [`Options::annotation`] is recursive,
+	/// so annotated output is limited to 128 levels regardless.
+	pub fn max_depth(mut self, max_depth: usize) -> Self {
+		self.max_depth = max_depth;
+		self
+	}
+
+	/// Fail with [`Error::InputTooLarge`](crate::Error::InputTooLarge) when the JSON input
+	/// is longer than this many bytes, unlimited by default
+	pub fn max_input_size(mut self, max_input_size: usize) -> Self {
+		self.max_input_size = max_input_size;
+		self
+	}
+
+	/// Fail with [`Error::OutputTooLarge`](crate::Error::OutputTooLarge) instead of writing
+	/// more than this many bytes, unlimited by default
+	pub fn max_output_size(mut self, max_output_size: usize) -> Self {
+		self.max_output_size = max_output_size;
+		self
+	}
+
+	/// Fail with [`Error::StringTooLong`](crate::Error::StringTooLong) when a string or key
+	/// is longer than this many bytes, unlimited by default
+	pub fn max_string_length(mut self, max_string_length: usize) -> Self {
+		self.max_string_length = max_string_length;
+		self
+	}
+
+	/// Fail with [`Error::ArrayTooLong`](crate::Error::ArrayTooLong) when an array has more
+	/// elements than this, unlimited by default
+	pub fn max_array_length(mut self, max_array_length: usize) -> Self {
+		self.max_array_length = max_array_length;
+		self
+	}
+
+	/// Fail with [`Error::TooManyKeys`](crate::Error::TooManyKeys) when an object has more
+	/// keys than this, unlimited by default
+	pub fn max_object_keys(mut self, max_object_keys: usize) -> Self {
+		self.max_object_keys = max_object_keys;
+		self
+	}
+
+	/// Fail with [`Error::TooManyNodes`](crate::Error::TooManyNodes) when the input has more
+	/// values than this in total, unlimited by default
+	pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+		self.max_nodes = max_nodes;
+		self
+	}
+
+	/// Type definitions written before the table, names are checked with
+	/// [`LuaTarget::is_identifier`]
+	pub fn annotation(mut self, annotation: Annotation) -> Self {
+		self.annotation = annotation;
+		self
+	}
+
+	/// Lua chunk the table is wrapped in, names are checked with [`LuaTarget::is_identifier`]
+	///
+	/// Only applies to the `parse` functions, the serializer always writes a bare expression.
+	pub fn wrapper(mut self, wrapper: Wrapper) -> Self {
+		self.wrapper = wrapper;
+		self
+	}
+
+	/// Fail with [`Error::NotATable`](crate::Error::NotATable) when the root is not an array or object
+	pub fn require_table(mut self, require_table: bool) -> Self {
+		self.require_table = require_table;
+		self
+	}
+}
+
+impl Options {
+	pub(crate) fn is_compact(&self) -> bool {
+		self.layout == Layout::Compact
+	}
+
+	/// Line break placed before every entry and closing brace
+	pub(crate) fn line_break(&self) -> &'static str {
+		match self.is_compact() {
+			true => "",
+			false => self.newline.as_str(),
+		}
+	}
+
+	/// Indent characters added per nesting level
+	pub(crate) fn indent_size(&self) -> usize {
+		match self.is_compact() {
+			true => 0,
+			false => self.indent_width,
+		}
+	}
+
+	/// Whether the last entry of a table gets a separator
+	pub(crate) fn trailing_separator(&self) -> bool {
+		self.trailing_comma == TrailingComma::Always && !self.is_compact()
+	}
+
+	/// Text between a key and its value
+	pub(crate) fn assignment(&self) -> &'static str {
+		match self.is_compact() {
+			true => "=",
+			false => " = ",
+		}
+	}
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			indent_char: '\t',
+			indent_width: 1,
+			newline: Newline::default(),
+			separator: Separator::default(),
+			trailing_comma: TrailingComma::default(),
+			key_style: KeyStyle::default(),
+			target: LuaTarget::default(),
+			null_policy: NullPolicy::default(),
+			ascii_only: false,
+			require_table: false,
+			length_field: false,
+			wrapper: Wrapper::default(),
+			layout: Layout::default(),
+			key_order: KeyOrder::default(),
+			max_depth: usize::MAX,
+			max_input_size: usize::MAX,
+			max_output_size: usize::MAX,
+			max_string_length: usize::MAX,
+			max_array_length: usize::MAX,
+			max_object_keys: usize::MAX,
+			max_nodes: usize::MAX,
+			annotation: Annotation::default(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::KeyOrder;
+
+	#[test]
+	fn natural_order() {
+		let mut keys = [
+			"item10", "item2", "item02", "Item1", "item", "a1b2", "a1b10", "10", "9",
+		];
+
+		keys.sort_by(|a, b| KeyOrder::Natural.compare(a, b));
+
+		assert_eq!(
+			keys,
+			["9", "10", "Item1", "a1b2", "a1b10", "item", "item02", "item2", "item10"]
+		);
+	}
+}
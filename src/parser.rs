@@ -0,0 +1,961 @@
+use serde_json::{Map, Number, Value};
+use std::{
+	collections::HashMap,
+	fmt::{self, Display},
+};
+
+use crate::{Error, Result};
+
+/// Table key, positional entries get their implicit index
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Key {
+	Integer(i64),
+	String(String),
+}
+
+/// Parsed Lua value along with where it starts in the source
+#[derive(Debug, Clone)]
+pub(crate) struct Node {
+	pub(crate) line: usize,
+	pub(crate) column: usize,
+	pub(crate) kind: Kind,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Kind {
+	Nil,
+	Bool(bool),
+	Number(Number),
+	String(String),
+	Table(Vec<(Key, Node)>),
+}
+
+impl Node {
+	pub(crate) fn into_json(self) -> Value {
+		match self.kind {
+			Kind::Nil => Value::Null,
+			Kind::Bool(bool) => Value::Bool(bool),
+			Kind::Number(number) => Value::Number(number),
+			Kind::String(string) => Value::String(string),
+			Kind::Table(entries) => match into_sequence(entries) {
+				Ok(values) => Value::Array(values.into_iter().map(Node::into_json).collect()),
+				Err(entries) => {
+					let mut map = Map::new();
+
+					for (key, value) in entries {
+						map.insert(key.to_string(), value.into_json());
+					}
+
+					Value::Object(map)
+				}
+			},
+		}
+	}
+}
+
+impl Display for Key {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Key::Integer(key) => key.fmt(f),
+			Key::String(key) => key.fmt(f),
+		}
+	}
+}
+
+/// Deepest table nesting accepted, close to the C stack limit of the reference Lua
+const MAX_DEPTH: usize = 200;
+
+/// Recursive descent parser for Lua data expressions
+pub(crate) struct Parser<'a> {
+	bytes: &'a [u8],
+	position: usize,
+	line: usize,
+	column: usize,
+	depth: usize,
+}
+
+impl<'a> Parser<'a> {
+	pub(crate) fn new(lua: &'a str) -> Self {
+		Self {
+			bytes: lua.as_bytes(),
+			position: 0,
+			line: 1,
+			column: 1,
+			depth: 0,
+		}
+	}
+
+	/// Parse the whole input as a single, optionally returned, expression
+	pub(crate) fn parse(mut self) -> Result<Node> {
+		self.skip_trivia()?;
+
+		let checkpoint = self.checkpoint();
+
+		if self.identifier().as_deref() != Some("return") {
+			self.restore(checkpoint);
+		}
+
+		let value = self.value()?;
+
+		self.skip_trivia()?;
+
+		if self.peek() == Some(b';') {
+			self.bump();
+			self.skip_trivia()?;
+		}
+
+		match self.peek() {
+			Some(_) => Err(self.error("unexpected trailing characters")),
+			None => Ok(value),
+		}
+	}
+
+	fn value(&mut self) -> Result<Node> {
+		self.skip_trivia()?;
+
+		let (line, column) = (self.line, self.column);
+
+		let kind = match self.peek() {
+			Some(b'{') => self.table()?,
+			Some(b'"' | b'\'') => Kind::String(self.short_string()?),
+			Some(b'[') if matches!(self.peek_at(1), Some(b'[' | b'=')) => {
+				Kind::String(self.long_string()?)
+			}
+			Some(b'-') => {
+				self.bump();
+				self.skip_trivia()?;
+
+				match self.peek() {
+					Some(b'0'..=b'9' | b'.') => Kind::Number(negate(self.number()?)),
+					_ => return Err(self.error("expected number after `-`")),
+				}
+			}
+			Some(b'0'..=b'9') => Kind::Number(self.number()?),
+			Some(b'.') if matches!(self.peek_at(1), Some(b'0'..=b'9')) => {
+				Kind::Number(self.number()?)
+			}
+			Some(char) if is_identifier_start(char) => match self.identifier().as_deref() {
+				Some("true") => Kind::Bool(true),
+				Some("false") => Kind::Bool(false),
+				Some("nil") => Kind::Nil,
+				Some(name) => {
+					return Err(error_at(
+						line,
+						column,
+						format!(
+							"unexpected identifier `{name}`, only literal values are supported"
+						),
+					))
+				}
+				None => unreachable!(),
+			},
+			Some(_) => return Err(self.error("expected value")),
+			None => return Err(self.error("unexpected end of input")),
+		};
+
+		Ok(Node { line, column, kind })
+	}
+
+	fn table(&mut self) -> Result<Kind> {
+		let mut entries: Vec<(Key, Node)> = Vec::new();
+		let mut positional = Vec::new();
+		// Where each explicit key is in `entries`
+		let mut explicit: HashMap<Key, usize> = HashMap::new();
+		let mut index = 0;
+
+		// Every level recurses, so bound it before hostile input overflows the stack
+		if self.depth == MAX_DEPTH {
+			return Err(self.error(format!("tables nested deeper than {MAX_DEPTH} levels")));
+		}
+
+		self.depth += 1;
+		self.bump();
+
+		loop {
+			self.skip_trivia()?;
+
+			if self.peek() == Some(b'}') {
+				self.bump();
+				break;
+			}
+
+			match self.field()? {
+				(Some(key), value) => match explicit.get(&key) {
+					// Later assignments to the same key overwrite earlier ones
+					Some(&i) => entries[i].1 = value,
+					None => {
+						explicit.insert(key.clone(), entries.len());
+						positional.push(false);
+						entries.push((key, value));
+					}
+				},
+				(None, value) => {
+					index += 1;
+					positional.push(true);
+					entries.push((Key::Integer(index), value));
+				}
+			}
+
+			self.skip_trivia()?;
+
+			match self.peek() {
+				Some(b',' | b';') => self.bump(),
+				Some(b'}') => {}
+				Some(_) => return Err(self.error("expected `,`, `;` or `}`")),
+				None => return Err(self.error("unclosed table, expected `}`")),
+			}
+		}
+
+		self.depth -= 1;
+
+		// Lua assigns positional entries last, so they win over explicit indices
+		if index > 0 {
+			let mut positional = positional.into_iter();
+
+			entries.retain(|(key, _)| {
+				positional.next().unwrap_or(true)
+					|| !matches!(key, Key::Integer(key) if (1..=index).contains(key))
+			});
+		}
+
+		Ok(Kind::Table(entries))
+	}
+
+	fn field(&mut self) -> Result<(Option<Key>, Node)> {
+		match self.peek() {
+			Some(b'[') if !matches!(self.peek_at(1), Some(b'[' | b'=')) => {
+				let (line, column) = (self.line, self.column);
+
+				self.bump();
+
+				let key = match self.value()?.kind {
+					Kind::String(key) => Key::String(key),
+					Kind::Number(key) => match key.as_i64() {
+						Some(key) => Key::Integer(key),
+						None => match key.as_f64() {
+							Some(float) if float.fract() == 0.0 && float.abs() < 2f64.powi(63) => {
+								Key::Integer(float as i64)
+							}
+							_ => Key::String(key.to_string()),
+						},
+					},
+					Kind::Nil => return Err(error_at(line, column, "table key can't be nil")),
+					_ => {
+						return Err(error_at(
+							line,
+							column,
+							"table key has to be a string or a number",
+						))
+					}
+				};
+
+				self.expect(b']')?;
+				self.expect(b'=')?;
+
+				Ok((Some(key), self.value()?))
+			}
+			Some(char) if is_identifier_start(char) => {
+				let checkpoint = self.checkpoint();
+				let name = self.identifier().unwrap_or_default();
+
+				self.skip_trivia()?;
+
+				if self.peek() == Some(b'=') && self.peek_at(1) != Some(b'=') {
+					self.bump();
+					Ok((Some(Key::String(name)), self.value()?))
+				} else {
+					self.restore(checkpoint);
+					Ok((None, self.value()?))
+				}
+			}
+			_ => Ok((None, self.value()?)),
+		}
+	}
+
+	fn short_string(&mut self) -> Result<String> {
+		let (line, column) = (self.line, self.column);
+		let quote = self.bump_byte();
+		let mut string = Vec::new();
+
+		loop {
+			match self.peek() {
+				Some(char) if char == quote => {
+					self.bump();
+					break;
+				}
+				Some(b'\\') => {
+					self.bump();
+					self.escape(&mut string)?;
+				}
+				Some(b'\n' | b'\r') | None => {
+					return Err(error_at(line, column, "unfinished string"));
+				}
+				Some(_) => string.push(self.bump_byte()),
+			}
+		}
+
+		String::from_utf8(string).map_err(|_| error_at(line, column, "string is not valid UTF-8"))
+	}
+
+	fn escape(&mut self, string: &mut Vec<u8>) -> Result<()> {
+		let (line, column) = (self.line, self.column - 1);
+
+		let char = match self.peek() {
+			Some(char) => char,
+			None => return Err(self.error("unfinished string")),
+		};
+
+		match char {
+			b'a' => string.push(b'\x07'),
+			b'b' => string.push(b'\x08'),
+			b'f' => string.push(b'\x0C'),
+			b'n' => string.push(b'\n'),
+			b'r' => string.push(b'\r'),
+			b't' => string.push(b'\t'),
+			b'v' => string.push(b'\x0B'),
+			b'\\' | b'"' | b'\'' => string.push(char),
+			b'\n' | b'\r' => {
+				self.newline();
+				string.push(b'\n');
+				return Ok(());
+			}
+			b'z' => {
+				self.bump();
+
+				while let Some(char) = self.peek() {
+					match char {
+						b'\n' | b'\r' => self.newline(),
+						_ if char.is_ascii_whitespace() => self.bump(),
+						_ => break,
+					}
+				}
+
+				return Ok(());
+			}
+			b'x' => {
+				self.bump();
+
+				let mut byte = 0;
+
+				for _ in 0..2 {
+					match self.peek().and_then(|char| (char as char).to_digit(16)) {
+						Some(digit) => byte = byte * 16 + digit as u8,
+						None => return Err(error_at(line, column, "hexadecimal digit expected")),
+					}
+
+					self.bump();
+				}
+
+				string.push(byte);
+				return Ok(());
+			}
+			b'u' => {
+				self.bump();
+
+				if self.peek() != Some(b'{') {
+					return Err(error_at(line, column, "missing `{` in `\\u{XXXX}`"));
+				}
+
+				self.bump();
+
+				let mut code: u32 = 0;
+
+				while let Some(digit) = self.peek().and_then(|char| (char as char).to_digit(16)) {
+					code = code
+						.checked_mul(16)
+						.and_then(|code| code.checked_add(digit))
+						.ok_or_else(|| error_at(line, column, "UTF-8 value too large"))?;
+
+					self.bump();
+				}
+
+				if self.peek() != Some(b'}') {
+					return Err(error_at(line, column, "missing `}` in `\\u{XXXX}`"));
+				}
+
+				self.bump();
+
+				let char = char::from_u32(code)
+					.ok_or_else(|| error_at(line, column, "invalid Unicode code point"))?;
+
+				string.extend_from_slice(char.encode_utf8(&mut [0; 4]).as_bytes());
+				return Ok(());
+			}
+			b'0'..=b'9' => {
+				let mut byte: u32 = 0;
+
+				for _ in 0..3 {
+					match self.peek() {
+						Some(digit @ b'0'..=b'9') => byte = byte * 10 + (digit - b'0') as u32,
+						_ => break,
+					}
+
+					self.bump();
+				}
+
+				if byte > 255 {
+					return Err(error_at(line, column, "decimal escape too large"));
+				}
+
+				string.push(byte as u8);
+				return Ok(());
+			}
+			_ => return Err(error_at(line, column, "invalid escape sequence")),
+		}
+
+		self.bump();
+
+		Ok(())
+	}
+
+	fn long_string(&mut self) -> Result<String> {
+		let (line, column) = (self.line, self.column);
+		let level = self
+			.long_bracket()
+			.ok_or_else(|| self.error("invalid long string delimiter"))?;
+
+		// A newline right after the opening bracket is not part of the string
+		if matches!(self.peek(), Some(b'\n' | b'\r')) {
+			self.newline();
+		}
+
+		let mut string = Vec::new();
+
+		loop {
+			match self.peek() {
+				Some(b']') if self.closes_long_bracket(level) => {
+					for _ in 0..level + 2 {
+						self.bump();
+					}
+
+					break;
+				}
+				Some(b'\n' | b'\r') => {
+					self.newline();
+					string.push(b'\n');
+				}
+				Some(_) => string.push(self.bump_byte()),
+				None => return Err(error_at(line, column, "unfinished long string")),
+			}
+		}
+
+		String::from_utf8(string).map_err(|_| error_at(line, column, "string is not valid UTF-8"))
+	}
+
+	/// Consume `[==[` and return its level or `None` without consuming anything
+	fn long_bracket(&mut self) -> Option<usize> {
+		let mut level = 0;
+
+		while self.peek_at(level + 1) == Some(b'=') {
+			level += 1;
+		}
+
+		if self.peek() != Some(b'[') || self.peek_at(level + 1) != Some(b'[') {
+			return None;
+		}
+
+		for _ in 0..level + 2 {
+			self.bump();
+		}
+
+		Some(level)
+	}
+
+	fn closes_long_bracket(&self, level: usize) -> bool {
+		(1..=level).all(|i| self.peek_at(i) == Some(b'=')) && self.peek_at(level + 1) == Some(b']')
+	}
+
+	fn number(&mut self) -> Result<Number> {
+		let (line, column) = (self.line, self.column);
+		let start = self.position;
+
+		let hex = self.peek() == Some(b'0') && matches!(self.peek_at(1), Some(b'x' | b'X'));
+
+		if hex {
+			self.bump();
+			self.bump();
+		}
+
+		let (digits, exponent) = if hex { (16, b'p') } else { (10, b'e') };
+
+		while let Some(char) = self.peek() {
+			if (char as char).is_digit(digits) || char == b'.' {
+				self.bump();
+			} else if char.to_ascii_lowercase() == exponent {
+				self.bump();
+
+				if matches!(self.peek(), Some(b'+' | b'-')) {
+					self.bump();
+				}
+			} else {
+				break;
+			}
+		}
+
+		let literal = std::str::from_utf8(&self.bytes[start..self.position]).unwrap_or_default();
+
+		let suffix_start = self.position;
+
+		while self.peek().is_some_and(is_identifier_char) {
+			self.bump();
+		}
+
+		let suffix = std::str::from_utf8(&self.bytes[suffix_start..self.position])
+			.unwrap_or_default()
+			.to_ascii_lowercase();
+
+		let malformed = || {
+			error_at(
+				line,
+				column,
+				format!("malformed number near `{literal}{suffix}`"),
+			)
+		};
+
+		let number = match suffix.as_str() {
+			"" if hex => parse_hex(&literal[2..]),
+			"" => parse_decimal(literal),
+			// LuaJIT boxed 64-bit integers
+			"ll" if !literal.contains(['.', 'e', 'E', 'p', 'P']) => {
+				let value = if hex {
+					u64::from_str_radix(&literal[2..], 16)
+						.ok()
+						.map(|n| n as i64)
+				} else {
+					literal.parse::<i64>().ok()
+				};
+
+				value.map(Number::from)
+			}
+			"ull" if !literal.contains(['.', 'e', 'E', 'p', 'P']) => {
+				let value = if hex {
+					u64::from_str_radix(&literal[2..], 16).ok()
+				} else {
+					literal.parse::<u64>().ok()
+				};
+
+				value.map(Number::from)
+			}
+			_ => None,
+		};
+
+		number.ok_or_else(malformed)
+	}
+
+	fn identifier(&mut self) -> Option<String> {
+		if !self.peek().is_some_and(is_identifier_start) {
+			return None;
+		}
+
+		let start = self.position;
+
+		while self.peek().is_some_and(is_identifier_char) {
+			self.bump();
+		}
+
+		Some(String::from_utf8_lossy(&self.bytes[start..self.position]).into_owned())
+	}
+
+	fn skip_trivia(&mut self) -> Result<()> {
+		loop {
+			match self.peek() {
+				Some(b'\n' | b'\r') => self.newline(),
+				Some(char) if char.is_ascii_whitespace() => self.bump(),
+				Some(b'-') if self.peek_at(1) == Some(b'-') => {
+					let (line, column) = (self.line, self.column);
+
+					self.bump();
+					self.bump();
+
+					if let Some(level) = self.long_bracket() {
+						loop {
+							match self.peek() {
+								Some(b']') if self.closes_long_bracket(level) => {
+									for _ in 0..level + 2 {
+										self.bump();
+									}
+
+									break;
+								}
+								Some(b'\n' | b'\r') => self.newline(),
+								Some(_) => self.bump(),
+								None => {
+									return Err(error_at(line, column, "unfinished long comment"))
+								}
+							}
+						}
+					} else {
+						while !matches!(self.peek(), Some(b'\n' | b'\r') | None) {
+							self.bump();
+						}
+					}
+				}
+				_ => return Ok(()),
+			}
+		}
+	}
+
+	fn expect(&mut self, char: u8) -> Result<()> {
+		self.skip_trivia()?;
+
+		if self.peek() == Some(char) {
+			self.bump();
+			Ok(())
+		} else {
+			Err(self.error(format!("expected `{}`", char as char)))
+		}
+	}
+
+	fn peek(&self) -> Option<u8> {
+		self.bytes.get(self.position).copied()
+	}
+
+	fn peek_at(&self, offset: usize) -> Option<u8> {
+		self.bytes.get(self.position + offset).copied()
+	}
+
+	fn bump(&mut self) {
+		self.bump_byte();
+	}
+
+	fn bump_byte(&mut self) -> u8 {
+		let byte = self.bytes[self.position];
+
+		self.position += 1;
+
+		// Count characters, not bytes
+		if byte & 0xC0 != 0x80 {
+			self.column += 1;
+		}
+
+		byte
+	}
+
+	/// Consume `\n`, `\r`, `\r\n` or `\n\r` as a single line break
+	fn newline(&mut self) {
+		let first = self.bytes[self.position];
+
+		self.position += 1;
+
+		if matches!(self.peek(), Some(char @ (b'\n' | b'\r')) if char != first) {
+			self.position += 1;
+		}
+
+		self.line += 1;
+		self.column = 1;
+	}
+
+	fn checkpoint(&self) -> (usize, usize, usize) {
+		(self.position, self.line, self.column)
+	}
+
+	fn restore(&mut self, (position, line, column): (usize, usize, usize)) {
+		self.position = position;
+		self.line = line;
+		self.column = column;
+	}
+
+	fn error(&self, message: impl Into<String>) -> Error {
+		error_at(self.line, self.column, message)
+	}
+}
+
+fn error_at(line: usize, column: usize, message: impl Into<String>) -> Error {
+	Error::Lua {
+		line,
+		column,
+		message: message.into(),
+	}
+}
+
+fn is_identifier_start(char: u8) -> bool {
+	char.is_ascii_alphabetic() || char == b'_'
+}
+
+fn is_identifier_char(char: u8) -> bool {
+	char.is_ascii_alphanumeric() || char == b'_'
+}
+
+fn parse_decimal(literal: &str) -> Option<Number> {
+	if !literal.contains(['.', 'e', 'E']) {
+		if let Ok(int) = literal.parse::<u64>() {
+			return Some(Number::from(int));
+		}
+	}
+
+	literal.parse::<f64>().ok().and_then(Number::from_f64)
+}
+
+fn parse_hex(literal: &str) -> Option<Number> {
+	let (mantissa, exponent) = match literal.find(['p', 'P']) {
+		Some(index) => (
+			&literal[..index],
+			Some(literal[index + 1..].parse::<i32>().ok()?),
+		),
+		None => (literal, None),
+	};
+
+	let (int, fract) = match mantissa.find('.') {
+		Some(index) => (&mantissa[..index], Some(&mantissa[index + 1..])),
+		None => (mantissa, None),
+	};
+
+	if int.is_empty() && fract.is_none_or(str::is_empty) {
+		return None;
+	}
+
+	if fract.is_none() && exponent.is_none() {
+		// Hex integers wrap around like in Lua 5.3+
+		let mut value: u64 = 0;
+
+		for digit in int.chars() {
+			value = value
+				.wrapping_mul(16)
+				.wrapping_add(digit.to_digit(16)? as u64);
+		}
+
+		return Some(Number::from(value as i64));
+	}
+
+	let mut value = 0.0;
+
+	for digit in int.chars() {
+		value = value * 16.0 + digit.to_digit(16)? as f64;
+	}
+
+	let mut scale = 1.0 / 16.0;
+
+	for digit in fract.unwrap_or_default().chars() {
+		value += digit.to_digit(16)? as f64 * scale;
+		scale /= 16.0;
+	}
+
+	Number::from_f64(value * 2f64.powi(exponent.unwrap_or(0)))
+}
+
+fn negate(number: Number) -> Number {
+	// Integers wrap like in Lua, only hex literals can reach `i64::MIN` and it stays as is
+	if let Some(int) = number.as_i64() {
+		return Number::from(int.wrapping_neg());
+	}
+
+	if number.as_u64() == Some(1 << 63) {
+		return Number::from(i64::MIN);
+	}
+
+	Number::from_f64(-number.as_f64().unwrap_or_default()).unwrap_or(number)
+}
+
+/// Order entries by index when keys are exactly `1..=n`, give them back otherwise
+///
+/// An integer `n` field, like the one written by `table.pack` or
+/// [`Options::length_field`](crate::Options::length_field), marks the length so missing
+/// indices up to it are read as `nil`. It needs at least one index next to it, a table
+/// with only `n` stays a record.
+pub(crate) fn into_sequence(
+	entries: Vec<(Key, Node)>,
+) -> std::result::Result<Vec<Node>, Vec<(Key, Node)>> {
+	// Bound the holes so a stray `n` can't allocate an arbitrarily large array
+	const MAX_HOLES: usize = 1 << 16;
+
+	let length = entries
+		.iter()
+		.find_map(|(key, value)| match (key, &value.kind) {
+			(Key::String(key), Kind::Number(n)) if key == "n" => Some((n.as_u64()?, value)),
+			_ => None,
+		});
+
+	let (len, packed) = match length {
+		Some((n, _)) if (n as usize) < entries.len() + MAX_HOLES => (n as usize, true),
+		_ => (entries.len(), false),
+	};
+
+	let mut seen = vec![false; len];
+
+	for (key, _) in &entries {
+		match key {
+			Key::Integer(i) if *i >= 1 && *i as usize <= len && !seen[*i as usize - 1] => {
+				seen[*i as usize - 1] = true
+			}
+			Key::String(key) if packed && key == "n" => {}
+			_ => return Err(entries),
+		}
+	}
+
+	// Plain `{ n = 2 }` is far more likely to be a record than a packed array of nils
+	if !seen.contains(&true) || (!packed && seen.contains(&false)) {
+		return Err(entries);
+	}
+
+	let (line, column) = length.map_or((0, 0), |(_, n)| (n.line, n.column));
+	let mut values: Vec<Option<Node>> = (0..len).map(|_| None).collect();
+
+	for (key, value) in entries {
+		if let Key::Integer(i) = key {
+			values[i as usize - 1] = Some(value);
+		}
+	}
+
+	Ok(values
+		.into_iter()
+		.map(|value| {
+			value.unwrap_or(Node {
+				line,
+				column,
+				kind: Kind::Nil,
+			})
+		})
+		.collect())
+}
+
+#[cfg(test)]
+mod test {
+	use serde_json::json;
+
+	use crate::{lua_to_value, parse, parse_with, Error, NullPolicy, Options};
+
+	#[test]
+	fn round_trip() {
+		let json = json!({
+			"string": "a\"b\\c\n\u{0}\u{1b}",
+			"int": 420,
+			"float": 4.2,
+			"negative": -7,
+			"bool": true,
+			"null": null,
+			"array": ["a", 1, false, { "k": "v" }, []],
+			"object": { "key": "value" },
+		});
+
+		let lua = parse(&json.to_string()).unwrap();
+
+		let mut expected = json.clone();
+		// Empty tables are read back as objects
+		expected["array"][4] = json!({});
+
+		assert_eq!(lua_to_value(&lua).unwrap(), expected);
+	}
+
+	#[test]
+	fn hand_written() {
+		let lua = r#"
+-- Item definitions
+return {
+	name = 'sword', --[[ inline ]] [ "damage" ] = 0x1F;
+	tags = { [1] = [[sharp]], [2] = [==[
+heavy]==] },
+	weight = -.5e1,
+	id = 0xFFULL,
+	escapes = "\65\x42\u{43}\z
+	           D",
+}
+"#;
+
+		assert_eq!(
+			lua_to_value(lua).unwrap(),
+			json!({
+				"name": "sword",
+				"damage": 31,
+				"tags": ["sharp", "heavy"],
+				"weight": -5.0,
+				"id": 255,
+				"escapes": "ABCD",
+			})
+		);
+
+		// Integers wrap around like in Lua 5.3+
+		assert_eq!(
+			lua_to_value("{ -0x8000000000000000, -0x7FFFFFFFFFFFFFFF, -0xFFFFFFFFFFFFFFFF }")
+				.unwrap(),
+			json!([i64::MIN, -i64::MAX, 1])
+		);
+	}
+
+	#[test]
+	fn packed_length() {
+		assert_eq!(
+			lua_to_value("{ [1] = 1, [3] = 3, n = 4 }").unwrap(),
+			json!([1, null, 3, null])
+		);
+		assert_eq!(lua_to_value("{ n = 2 }").unwrap(), json!({ "n": 2 }));
+		assert_eq!(
+			lua_to_value("{ [1] = 1, [3] = 3 }").unwrap(),
+			json!({ "1": 1, "3": 3 })
+		);
+		assert_eq!(
+			lua_to_value("{ [5] = 1, n = 2 }").unwrap(),
+			json!({ "5": 1, "n": 2 })
+		);
+
+		let options = Options::new()
+			.null_policy(NullPolicy::Indexed)
+			.length_field(true);
+
+		let lua = parse_with("[1, null, null]", &options).unwrap();
+		assert_eq!(lua_to_value(&lua).unwrap(), json!([1, null, null]));
+
+		// Nothing but the length is left of all-null arrays, which reads as a record
+		let lua = parse_with("[null, null]", &options).unwrap();
+		assert_eq!(lua_to_value(&lua).unwrap(), json!({ "n": 2 }));
+	}
+
+	#[test]
+	fn overwritten_keys() {
+		assert_eq!(lua_to_value("{ [1] = 'x', 'y' }").unwrap(), json!(["y"]));
+		assert_eq!(lua_to_value("{ 'y', [1] = 'x' }").unwrap(), json!(["y"]));
+		assert_eq!(
+			lua_to_value("{ [2] = 'x', 'y', 'z', [3] = 3 }").unwrap(),
+			json!(["y", "z", 3])
+		);
+		assert_eq!(
+			lua_to_value("{ [1] = 'a', [1] = 'b' }").unwrap(),
+			json!(["b"])
+		);
+		assert_eq!(
+			lua_to_value("{ a = 1, b = 2, a = 3 }").unwrap(),
+			json!({ "a": 3, "b": 2 })
+		);
+	}
+
+	#[test]
+	fn nesting_limit() {
+		let lua = format!("{}{}", "{".repeat(200), "}".repeat(200));
+		assert!(lua_to_value(&lua).is_ok());
+
+		let lua = format!("{}{}", "{".repeat(200_000), "}".repeat(200_000));
+
+		assert!(matches!(
+			lua_to_value(&lua).unwrap_err(),
+			Error::Lua {
+				line: 1,
+				column: 201,
+				..
+			}
+		));
+	}
+
+	#[test]
+	fn error_position() {
+		let err = lua_to_value("{\n\ta = 1,\n\tb = foo,\n}").unwrap_err();
+
+		assert!(matches!(
+			err,
+			Error::Lua {
+				line: 3,
+				column: 6,
+				..
+			}
+		));
+
+		let err = lua_to_value("{ 'unfinished }").unwrap_err();
+
+		assert!(matches!(
+			err,
+			Error::Lua {
+				line: 1,
+				column: 3,
+				..
+			}
+		));
+	}
+}
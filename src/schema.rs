@@ -0,0 +1,903 @@
+//! Generate Luau types and a validator from a JSON Schema
+//!
+//! Supports a subset of draft 2020-12: `type`, `properties`, `required`, `enum`, `items`,
+//! `oneOf` and `$ref` to `#/$defs` or `#/definitions`. Other keywords are ignored. The
+//! `true` schema matches anything and `false` matches nothing, so a `false` property may
+//! only be missing.
+//!
+//! ```rust
+//! use json2lua::{schema, Options};
+//!
+//! let schema = r#"{
+//! 	"type": "object",
+//! 	"properties": {
+//! 		"name": { "type": "string" },
+//! 		"mode": { "enum": ["fast", "slow"] }
+//! 	},
+//! 	"required": ["name"]
+//! }"#;
+//!
+//! let luau = schema::generate(schema, "Config", &Options::new()).unwrap();
+//!
+//! assert!(luau.starts_with("export type Config = {\n\tname: string,\n\tmode: (\"fast\" | \"slow\")?,\n}"));
+//! assert!(luau.ends_with("return validate"));
+//! ```
+
+use serde_json::{Map, Value};
+use std::{
+	collections::{HashMap, HashSet},
+	fmt::{self, Display, Write},
+};
+
+use crate::{
+	escape::quote,
+	types::{pascal_case, Dialect, Field, Type, Writer},
+	Error, LuaTarget, Options, Result,
+};
+
+/// Generate Luau type aliases for the schema and its definitions, followed by a validator
+///
+/// The chunk returns `validate(value: any): (boolean, string?)`, which checks a decoded
+/// value against the schema and explains the first mismatch, e.g.
+/// `$.size.x: expected number`. JSON `null` is `nil` in Lua, so a required property may
+/// only be missing when its schema allows `null`.
+pub fn generate(schema: &str, name: &str, options: &Options) -> Result<String> {
+	let schema: Value = serde_json::from_str(schema).map_err(|err| Error::syntax(err, schema))?;
+
+	if !LuaTarget::Luau.is_identifier(name) {
+		return Err(Error::InvalidName(name.to_owned()));
+	}
+
+	if Dialect::Luau.is_builtin(name) {
+		return Err(Error::InvalidName(name.to_owned()));
+	}
+
+	let mut definitions = Vec::new();
+	let mut references = HashMap::new();
+	let mut used = HashSet::from([name.to_owned()]);
+
+	if let Value::Object(root) = &schema {
+		for keyword in ["$defs", "definitions"] {
+			let Some(schemas) = root.get(keyword) else {
+				continue;
+			};
+
+			let pointer = child("", keyword);
+			let schemas = schemas
+				.as_object()
+				.ok_or_else(|| invalid(&pointer, "expected an object"))?;
+
+			for (key, schema) in schemas {
+				let pointer = child(&pointer, key);
+
+				let alias = match LuaTarget::Luau.is_identifier(key) {
+					true => key.clone(),
+					false => pascal_case(key),
+				};
+
+				if !LuaTarget::Luau.is_identifier(&alias) || Dialect::Luau.is_builtin(&alias) {
+					return Err(invalid(
+						&pointer,
+						"definition name can't be used as a type name",
+					));
+				}
+
+				// Different keys like `a-b` and `AB` can end up with the same name
+				if !used.insert(alias.clone()) {
+					return Err(invalid(
+						&pointer,
+						format_args!("definition name clashes with another type `{alias}`"),
+					));
+				}
+
+				references.insert(format!("#{pointer}"), alias.clone());
+				definitions.push((alias, schema, pointer));
+			}
+		}
+	}
+
+	let compiler = Compiler {
+		references: &references,
+	};
+
+	let definitions = definitions
+		.into_iter()
+		.map(|(alias, schema, pointer)| Ok((alias, compiler.node(schema, &pointer)?)))
+		.collect::<Result<Vec<_>>>()?;
+
+	let root = compiler.node(&schema, "")?;
+
+	let mut generator = Generator {
+		lua: String::new(),
+		options,
+		indent: String::new(),
+	};
+
+	for (alias, node) in &definitions {
+		generator.alias(alias, node)?;
+		generator.blank();
+	}
+
+	generator.alias(name, &root)?;
+	generator.blank();
+
+	if !definitions.is_empty() {
+		let names: Vec<String> = definitions
+			.iter()
+			.map(|(alias, _)| format!("check_{alias}"))
+			.collect();
+
+		// Definitions can refer to each other in any order
+		generator.line(format_args!("local {}", names.join(", ")));
+		generator.blank();
+
+		for (alias, node) in &definitions {
+			generator.open(format_args!(
+				"function check_{alias}(value: any, path: string): (boolean, string?)"
+			));
+			generator.checks(node);
+			generator.line("return true");
+			generator.close("end");
+			generator.blank();
+		}
+	}
+
+	generator.open("local function validate(value: any): (boolean, string?)");
+	generator.line("local path = \"$\"");
+	generator.checks(&root);
+	generator.line("return true");
+	generator.close("end");
+	generator.blank();
+	generator.line("return validate");
+
+	Ok(generator.lua)
+}
+
+fn invalid(pointer: &str, message: impl Display) -> Error {
+	Error::Schema {
+		pointer: pointer.to_owned(),
+		message: message.to_string(),
+	}
+}
+
+fn child(pointer: &str, segment: &str) -> String {
+	let segment = segment.replace('~', "~0").replace('/', "~1");
+	format!("{pointer}/{segment}")
+}
+
+/// Value of a JSON Schema `type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+	Null,
+	Boolean,
+	Object,
+	Array,
+	Number,
+	Integer,
+	String,
+}
+
+impl Kind {
+	fn parse(name: &str) -> Option<Kind> {
+		Some(match name {
+			"null" => Kind::Null,
+			"boolean" => Kind::Boolean,
+			"object" => Kind::Object,
+			"array" => Kind::Array,
+			"number" => Kind::Number,
+			"integer" => Kind::Integer,
+			"string" => Kind::String,
+			_ => return None,
+		})
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			Kind::Null => "null",
+			Kind::Boolean => "boolean",
+			Kind::Object => "object",
+			Kind::Array => "array",
+			Kind::Number => "number",
+			Kind::Integer => "integer",
+			Kind::String => "string",
+		}
+	}
+
+	/// Lua condition that holds when `value` is of this kind
+	fn condition(self) -> &'static str {
+		match self {
+			Kind::Null => "value == nil",
+			Kind::Boolean => "type(value) == \"boolean\"",
+			Kind::Object | Kind::Array => "type(value) == \"table\"",
+			Kind::Number => "type(value) == \"number\"",
+			Kind::Integer => "type(value) == \"number\" and value % 1 == 0",
+			Kind::String => "type(value) == \"string\"",
+		}
+	}
+
+	/// Negation of [`Kind::condition`]
+	fn mismatch(self) -> &'static str {
+		match self {
+			Kind::Null => "value ~= nil",
+			Kind::Boolean => "type(value) ~= \"boolean\"",
+			Kind::Object | Kind::Array => "type(value) ~= \"table\"",
+			Kind::Number => "type(value) ~= \"number\"",
+			Kind::Integer => "type(value) ~= \"number\" or value % 1 ~= 0",
+			Kind::String => "type(value) ~= \"string\"",
+		}
+	}
+}
+
+/// Schema with the supported keywords parsed and references resolved to type names
+#[derive(Debug, Default)]
+struct Node {
+	kinds: Vec<Kind>,
+	values: Option<Vec<Value>>,
+	properties: Vec<Property>,
+	items: Option<Box<Node>>,
+	one_of: Vec<Node>,
+	reference: Option<String>,
+	/// Set by the `false` schema, which no value matches
+	never: bool,
+}
+
+#[derive(Debug)]
+struct Property {
+	name: String,
+	node: Node,
+	required: bool,
+}
+
+impl Node {
+	/// Whether any value matches
+	fn is_trivial(&self) -> bool {
+		self.kinds.is_empty()
+			&& self.values.is_none()
+			&& self.properties.is_empty()
+			&& self.items.is_none()
+			&& self.one_of.is_empty()
+			&& self.reference.is_none()
+			&& !self.never
+	}
+
+	/// Whether `nil` matches, assuming it doesn't for references
+	fn allows_nil(&self) -> bool {
+		self.is_trivial()
+			|| self.kinds.contains(&Kind::Null)
+			|| self
+				.values
+				.as_ref()
+				.is_some_and(|values| values.contains(&Value::Null))
+			|| self.one_of.iter().any(Node::allows_nil)
+	}
+
+	fn ty(&self) -> Type {
+		if self.never {
+			return Type::Named(String::from("never"));
+		}
+
+		if let Some(name) = &self.reference {
+			return Type::Named(name.clone());
+		}
+
+		if let Some(values) = &self.values {
+			let strings: Vec<String> = values
+				.iter()
+				.filter_map(|value| value.as_str().map(String::from))
+				.collect();
+
+			let others = values.iter().map(|value| match value {
+				Value::Null => Type::Nil,
+				Value::Bool(_) => Type::Boolean,
+				Value::Number(_) => Type::Number,
+				_ => Type::Unknown,
+			});
+
+			let strings = (!strings.is_empty()).then_some(Type::Literals(strings));
+
+			return Type::union(strings.into_iter().chain(others));
+		}
+
+		if !self.one_of.is_empty() {
+			return Type::union(self.one_of.iter().map(Node::ty));
+		}
+
+		if self.kinds.is_empty() {
+			return match (self.properties.is_empty(), &self.items) {
+				(false, _) => self.record(),
+				(true, Some(items)) => Type::Array(Box::new(items.ty())),
+				(true, None) => Type::Unknown,
+			};
+		}
+
+		Type::union(self.kinds.iter().map(|kind| {
+			match kind {
+				Kind::Null => Type::Nil,
+				Kind::Boolean => Type::Boolean,
+				Kind::Object => self.record(),
+				Kind::Array => Type::Array(Box::new(
+					self.items
+						.as_ref()
+						.map_or(Type::Unknown, |items| items.ty()),
+				)),
+				Kind::Number => Type::Number,
+				Kind::Integer => Type::Integer,
+				Kind::String => Type::String,
+			}
+		}))
+	}
+
+	fn record(&self) -> Type {
+		Type::Record(
+			self.properties
+				.iter()
+				.map(|property| Field {
+					name: property.name.clone(),
+					ty: property.node.ty(),
+					optional: !property.required,
+				})
+				.collect(),
+		)
+	}
+}
+
+struct Compiler<'a> {
+	/// Type names by `$ref`
+	references: &'a HashMap<String, String>,
+}
+
+impl Compiler<'_> {
+	fn node(&self, schema: &Value, pointer: &str) -> Result<Node> {
+		let schema = match schema {
+			Value::Bool(true) => return Ok(Node::default()),
+			Value::Bool(false) => {
+				return Ok(Node {
+					never: true,
+					..Node::default()
+				})
+			}
+			Value::Object(schema) => schema,
+			_ => return Err(invalid(pointer, "expected an object or a boolean")),
+		};
+
+		let mut node = Node::default();
+
+		if let Some(reference) = schema.get("$ref") {
+			let name = reference
+				.as_str()
+				.and_then(|reference| self.references.get(reference))
+				.ok_or_else(|| {
+					invalid(
+						&child(pointer, "$ref"),
+						"expected a reference to `#/$defs` or `#/definitions`",
+					)
+				})?;
+
+			node.reference = Some(name.clone());
+		}
+
+		match schema.get("type") {
+			None => {}
+			Some(Value::String(name)) => node.kinds.push(kind(name, &child(pointer, "type"))?),
+			Some(Value::Array(names)) => {
+				for (i, name) in names.iter().enumerate() {
+					let pointer = child(&child(pointer, "type"), &i.to_string());
+					let name = name
+						.as_str()
+						.ok_or_else(|| invalid(&pointer, "expected a string"))?;
+
+					node.kinds.push(kind(name, &pointer)?);
+				}
+			}
+			Some(_) => {
+				return Err(invalid(
+					&child(pointer, "type"),
+					"expected a string or an array",
+				))
+			}
+		}
+
+		if let Some(values) = schema.get("enum") {
+			let pointer = child(pointer, "enum");
+			let values = values
+				.as_array()
+				.ok_or_else(|| invalid(&pointer, "expected an array"))?;
+
+			if let Some(i) = values
+				.iter()
+				.position(|value| value.is_array() || value.is_object())
+			{
+				return Err(invalid(
+					&child(&pointer, &i.to_string()),
+					"only strings, numbers, booleans and null are supported",
+				));
+			}
+
+			node.values = Some(values.clone());
+		}
+
+		let required = required(schema, pointer)?;
+
+		if let Some(properties) = schema.get("properties") {
+			let pointer = child(pointer, "properties");
+			let properties = properties
+				.as_object()
+				.ok_or_else(|| invalid(&pointer, "expected an object"))?;
+
+			for (name, schema) in properties {
+				node.properties.push(Property {
+					name: name.clone(),
+					node: self.node(schema, &child(&pointer, name))?,
+					required: required.contains(&name.as_str()),
+				});
+			}
+		}
+
+		// Required properties without a schema still have to be present
+		for name in required {
+			if !node.properties.iter().any(|property| property.name == name) {
+				node.properties.push(Property {
+					name: name.to_owned(),
+					node: Node::default(),
+					required: true,
+				});
+			}
+		}
+
+		if let Some(items) = schema.get("items") {
+			node.items = Some(Box::new(self.node(items, &child(pointer, "items"))?));
+		}
+
+		if let Some(one_of) = schema.get("oneOf") {
+			let pointer = child(pointer, "oneOf");
+			let one_of = one_of
+				.as_array()
+				.ok_or_else(|| invalid(&pointer, "expected an array"))?;
+
+			for (i, schema) in one_of.iter().enumerate() {
+				node.one_of
+					.push(self.node(schema, &child(&pointer, &i.to_string()))?);
+			}
+		}
+
+		Ok(node)
+	}
+}
+
+fn kind(name: &str, pointer: &str) -> Result<Kind> {
+	Kind::parse(name).ok_or_else(|| invalid(pointer, format!("unknown type `{name}`")))
+}
+
+fn required<'s>(schema: &'s Map<String, Value>, pointer: &str) -> Result<Vec<&'s str>> {
+	let Some(required) = schema.get("required") else {
+		return Ok(Vec::new());
+	};
+
+	let pointer = child(pointer, "required");
+
+	required
+		.as_array()
+		.ok_or_else(|| invalid(&pointer, "expected an array"))?
+		.iter()
+		.enumerate()
+		.map(|(i, name)| {
+			name.as_str()
+				.ok_or_else(|| invalid(&child(&pointer, &i.to_string()), "expected a string"))
+		})
+		.collect()
+}
+
+/// Writes Lua statements line by line, following the indentation of [`Options`]
+struct Generator<'a> {
+	lua: String,
+	options: &'a Options,
+	indent: String,
+}
+
+impl Generator<'_> {
+	fn alias(&mut self, name: &str, node: &Node) -> fmt::Result {
+		self.line(format_args!("export type {name} = "));
+		Writer::new(&mut self.lua, self.options).luau(&node.ty())
+	}
+
+	/// Write statements that return `false` and a message when `value` doesn't match
+	fn checks(&mut self, node: &Node) {
+		// `return` has to end a block, so it gets one of its own
+		if node.never {
+			let message = quote(": not allowed", self.options);
+
+			self.open("do");
+			self.line(format_args!("return false, path .. {message}"));
+			self.close("end");
+			return;
+		}
+
+		if let Some(name) = &node.reference {
+			self.line(format_args!("local ok, err = check_{name}(value, path)"));
+			self.open("if not ok then");
+			self.line("return false, err");
+			self.close("end");
+		}
+
+		match node.kinds.as_slice() {
+			[] => {}
+			[kind] => self.fail(kind.mismatch(), format_args!("expected {}", kind.name())),
+			kinds => {
+				let conditions: Vec<&str> = kinds.iter().map(|kind| kind.condition()).collect();
+				let names: Vec<&str> = kinds.iter().map(|kind| kind.name()).collect();
+
+				self.fail(
+					format_args!("not ({})", conditions.join(" or ")),
+					format_args!("expected {}", names.join(" or ")),
+				);
+			}
+		}
+
+		if let Some(values) = &node.values {
+			let conditions: Vec<String> = values
+				.iter()
+				.map(|value| format!("value ~= {}", self.literal(value)))
+				.collect();
+			let names: Vec<String> = values.iter().map(Value::to_string).collect();
+
+			self.fail(
+				conditions.join(" and "),
+				format_args!("expected one of {}", names.join(", ")),
+			);
+		}
+
+		if !node.one_of.is_empty() {
+			self.one_of(&node.one_of);
+		}
+
+		let items = node.items.as_deref().filter(|items| !items.is_trivial());
+
+		if node.properties.is_empty() && items.is_none() {
+			return;
+		}
+
+		// Checking the kind already returned when the value can only be a table
+		let guarded = node.kinds.is_empty()
+			|| !node
+				.kinds
+				.iter()
+				.all(|kind| matches!(kind, Kind::Object | Kind::Array));
+
+		if guarded {
+			self.open("if type(value) == \"table\" then");
+		}
+
+		for property in &node.properties {
+			self.property(property);
+		}
+
+		if let Some(items) = items {
+			self.open("for index, item in ipairs(value) do");
+			self.line("local value, path = item, path .. \"[\" .. index .. \"]\"");
+			self.checks(items);
+			self.close("end");
+		}
+
+		if guarded {
+			self.close("end");
+		}
+	}
+
+	fn property(&mut self, property: &Property) {
+		let Property {
+			name,
+			node,
+			required,
+		} = property;
+
+		let required = *required && !node.allows_nil();
+
+		if node.is_trivial() && !required {
+			return;
+		}
+
+		let (access, segment) = match LuaTarget::Luau.is_identifier(name) {
+			true => (format!("value.{name}"), format!(".{name}")),
+			false => (
+				format!("value[{}]", quote(name, self.options)),
+				format!("[{}]", Value::from(name.as_str())),
+			),
+		};
+
+		self.open("do");
+		self.line(format_args!(
+			"local value, path = {access}, path .. {}",
+			quote(&segment, self.options)
+		));
+
+		if required {
+			self.fail("value == nil", "required");
+			self.checks(node);
+		} else if node.allows_nil() {
+			self.checks(node);
+		} else {
+			self.open("if value ~= nil then");
+			self.checks(node);
+			self.close("end");
+		}
+
+		self.close("end");
+	}
+
+	fn one_of(&mut self, one_of: &[Node]) {
+		self.open("do");
+		self.open("local branches = {");
+
+		for node in one_of {
+			self.open("function(value: any, path: string): (boolean, string?)");
+			self.checks(node);
+			self.line("return true");
+			self.close("end,");
+		}
+
+		self.close("}");
+		self.line("local matched = 0");
+		self.blank();
+		self.open("for _, branch in ipairs(branches) do");
+		self.open("if branch(value, path) then");
+		self.line("matched = matched + 1");
+		self.close("end");
+		self.close("end");
+		self.blank();
+		self.open("if matched ~= 1 then");
+
+		let message = format!(
+			": expected exactly one of {} schemas to match, got ",
+			one_of.len()
+		);
+
+		self.line(format_args!(
+			"return false, path .. {} .. matched",
+			quote(&message, self.options)
+		));
+		self.close("end");
+		self.close("end");
+	}
+
+	/// Return `false` and the message when the condition holds
+	fn fail(&mut self, condition: impl Display, message: impl Display) {
+		let message = quote(&format!(": {message}"), self.options);
+
+		self.open(format_args!("if {condition} then"));
+		self.line(format_args!("return false, path .. {message}"));
+		self.close("end");
+	}
+
+	fn literal(&self, value: &Value) -> String {
+		match value {
+			Value::Null => String::from("nil"),
+			Value::String(string) => quote(string, self.options),
+			value => value.to_string(),
+		}
+	}
+
+	/// Start a new line at the current indentation
+	fn line(&mut self, text: impl Display) {
+		if !self.lua.is_empty() {
+			match self.options.is_compact() {
+				true => self.lua.push(' '),
+				false => self.lua.push_str(self.options.newline.as_str()),
+			}
+
+			self.lua.push_str(&self.indent);
+		}
+
+		// Writing into a `String` can't fail
+		let _ = write!(self.lua, "{text}");
+	}
+
+	/// Write a line and indent the following ones
+	fn open(&mut self, text: impl Display) {
+		self.line(text);
+
+		for _ in 0..self.options.indent_size() {
+			self.indent.push(self.options.indent_char);
+		}
+	}
+
+	/// Dedent and write a line
+	fn close(&mut self, text: impl Display) {
+		let outer =
+			self.indent.len() - self.options.indent_size() * self.options.indent_char.len_utf8();
+
+		self.indent.truncate(outer);
+		self.line(text);
+	}
+
+	/// Leave an empty line unless the output is compact
+	fn blank(&mut self) {
+		if !self.options.is_compact() {
+			self.lua.push_str(self.options.newline.as_str());
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::generate;
+	use crate::{Error, Options};
+
+	#[test]
+	fn validator() {
+		let schema = r##"{
+			"definitions": {
+				"point": {
+					"type": "object",
+					"properties": { "x": { "type": "integer" } },
+					"required": ["x"]
+				}
+			},
+			"type": "array",
+			"items": {
+				"oneOf": [
+					{ "$ref": "#/definitions/point" },
+					{ "type": ["string", "null"], "enum": ["origin", null] }
+				]
+			}
+		}"##;
+
+		let lua = r#"export type point = {
+	x: number,
+}
+
+export type Points = {(point | "origin")?}
+
+local check_point
+
+function check_point(value: any, path: string): (boolean, string?)
+	if type(value) ~= "table" then
+		return false, path .. ": expected object"
+	end
+	do
+		local value, path = value.x, path .. ".x"
+		if value == nil then
+			return false, path .. ": required"
+		end
+		if type(value) ~= "number" or value % 1 ~= 0 then
+			return false, path .. ": expected integer"
+		end
+	end
+	return true
+end
+
+local function validate(value: any): (boolean, string?)
+	local path = "$"
+	if type(value) ~= "table" then
+		return false, path .. ": expected array"
+	end
+	for index, item in ipairs(value) do
+		local value, path = item, path .. "[" .. index .. "]"
+		do
+			local branches = {
+				function(value: any, path: string): (boolean, string?)
+					local ok, err = check_point(value, path)
+					if not ok then
+						return false, err
+					end
+					return true
+				end,
+				function(value: any, path: string): (boolean, string?)
+					if not (type(value) == "string" or value == nil) then
+						return false, path .. ": expected string or null"
+					end
+					if value ~= "origin" and value ~= nil then
+						return false, path .. ": expected one of \"origin\", null"
+					end
+					return true
+				end,
+			}
+			local matched = 0
+
+			for _, branch in ipairs(branches) do
+				if branch(value, path) then
+					matched = matched + 1
+				end
+			end
+
+			if matched ~= 1 then
+				return false, path .. ": expected exactly one of 2 schemas to match, got " .. matched
+			end
+		end
+	end
+	return true
+end
+
+return validate"#;
+
+		assert_eq!(generate(schema, "Points", &Options::new()).unwrap(), lua);
+	}
+
+	#[test]
+	fn invalid_schemas() {
+		let pointer = |schema: &str| match generate(schema, "Root", &Options::new()) {
+			Err(Error::Schema { pointer, .. }) => pointer,
+			result => panic!("expected schema error, got {result:?}"),
+		};
+
+		assert_eq!(pointer("1"), "");
+		assert_eq!(pointer(r#"{ "type": "map" }"#), "/type");
+		assert_eq!(pointer(r#"{ "type": ["string", 1] }"#), "/type/1");
+		assert_eq!(pointer(r#"{ "items": { "enum": [[]] } }"#), "/items/enum/0");
+		assert_eq!(pointer(r#"{ "required": [1] }"#), "/required/0");
+		assert_eq!(
+			pointer(r#"{ "properties": { "a/b": { "$ref": "other.json" } } }"#),
+			"/properties/a~1b/$ref"
+		);
+		assert_eq!(pointer(r#"{ "$defs": { "Root": {} } }"#), "/$defs/Root");
+		assert_eq!(pointer(r#"{ "oneOf": [true, 1] }"#), "/oneOf/1");
+		assert_eq!(
+			pointer(r#"{ "$defs": { "a-b": {}, "AB": {} } }"#),
+			"/$defs/AB"
+		);
+		assert_eq!(
+			pointer(r#"{ "$defs": { "Item": {} }, "definitions": { "Item": {} } }"#),
+			"/definitions/Item"
+		);
+		assert_eq!(pointer(r#"{ "$defs": { "string": {} } }"#), "/$defs/string");
+
+		assert!(matches!(
+			generate("{}", "number", &Options::new()),
+			Err(Error::InvalidName(_))
+		));
+
+		assert!(matches!(
+			generate("{}", "end", &Options::new()),
+			Err(Error::InvalidName(_))
+		));
+		assert!(matches!(
+			generate("{", "Root", &Options::new()),
+			Err(Error::Syntax { .. })
+		));
+	}
+
+	#[test]
+	fn false_schema() {
+		let schema = r#"{
+			"type": "object",
+			"properties": { "legacy": false, "tags": { "items": false } }
+		}"#;
+
+		let lua = r#"export type Root = {
+	legacy: never?,
+	tags: {never}?,
+}
+
+local function validate(value: any): (boolean, string?)
+	local path = "$"
+	if type(value) ~= "table" then
+		return false, path .. ": expected object"
+	end
+	do
+		local value, path = value.legacy, path .. ".legacy"
+		if value ~= nil then
+			do
+				return false, path .. ": not allowed"
+			end
+		end
+	end
+	do
+		local value, path = value.tags, path .. ".tags"
+		if value ~= nil then
+			if type(value) == "table" then
+				for index, item in ipairs(value) do
+					local value, path = item, path .. "[" .. index .. "]"
+					do
+						return false, path .. ": not allowed"
+					end
+				end
+			end
+		end
+	end
+	return true
+end
+
+return validate"#;
+
+		assert_eq!(generate(schema, "Root", &Options::new()).unwrap(), lua);
+	}
+}
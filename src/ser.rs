@@ -0,0 +1,911 @@
+//! Serialize Rust data structures directly into Lua
+//!
+//! ```rust
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Item {
+//! 	name: String,
+//! 	damage: u32,
+//! 	tags: Vec<&'static str>,
+//! }
+//!
+//! let item = Item {
+//! 	name: String::from("sword"),
+//! 	damage: 10,
+//! 	tags: vec!["sharp"],
+//! };
+//!
+//! let lua = r#"{
+//! 	["name"] = "sword",
+//! 	["damage"] = 10,
+//! 	["tags"] = {
+//! 		"sharp",
+//! 	},
+//! }"#;
+//!
+//! assert_eq!(json2lua::to_lua_string(&item).unwrap(), lua);
+//! ```
+
+use serde::ser::{self, Impossible, Serialize};
+use serde_json::Number;
+use std::{fmt::Write, io};
+
+use crate::{
+	emit::{get_key, null, write_key},
+	escape::quote,
+	Error, NullPolicy, Options, Result,
+};
+
+/// Serializer that writes Lua using the same formatting as [`parse_with`](crate::parse_with)
+pub struct Serializer<W> {
+	writer: W,
+	options: Options,
+	depth: usize,
+	/// Indentation of the current table, reused for every line
+	indent: String,
+	/// Separator, indentation and key of the entry that is about to be written, reused
+	/// for every entry
+	prefix: String,
+	/// Whether the prefix still has to be written, omitted values leave it unwritten
+	pending: bool,
+	/// Whether the pending entry is an array element rather than a keyed field
+	element: bool,
+}
+
+/// Key part of a table entry prefix
+enum Prefix<'k> {
+	Positional,
+	Index(usize),
+	/// Key that is written as an identifier or a quoted string
+	Name(&'k str),
+	/// Key that is already written as Lua, including the assignment
+	Lua(&'k str),
+}
+
+impl<W: io::Write> Serializer<W> {
+	/// Create serializer with default [`Options`]
+	pub fn new(writer: W) -> Self {
+		Self::with_options(writer, &Options::default())
+	}
+
+	/// Create serializer with custom formatting [`Options`]
+	pub fn with_options(writer: W, options: &Options) -> Self {
+		Self {
+			writer,
+			options: options.clone(),
+			depth: 0,
+			indent: String::new(),
+			prefix: String::new(),
+			pending: false,
+			element: false,
+		}
+	}
+
+	/// Unwrap the underlying writer
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+
+	fn write(&mut self, lua: &str) -> Result<()> {
+		self.flush_prefix()?;
+		self.writer.write_all(lua.as_bytes())?;
+
+		Ok(())
+	}
+
+	fn flush_prefix(&mut self) -> Result<()> {
+		if self.pending {
+			self.pending = false;
+			self.writer.write_all(self.prefix.as_bytes())?;
+		}
+
+		Ok(())
+	}
+
+	fn write_number(&mut self, number: Number) -> Result<()> {
+		let number = self.options.target.number(&number);
+		self.write(&number)
+	}
+
+	fn write_float(&mut self, float: f64) -> Result<()> {
+		match Number::from_f64(float) {
+			Some(number) => self.write_number(number),
+			None if float.is_nan() => self.write("0/0"),
+			None if float > 0.0 => self.write("1/0"),
+			None => self.write("-1/0"),
+		}
+	}
+
+	fn begin_table(&mut self) -> Result<()> {
+		if self.depth >= self.options.max_depth {
+			return Err(Error::TooDeep {
+				pointer: String::new(),
+			});
+		}
+
+		self.write("{")?;
+		self.depth += 1;
+
+		for _ in 0..self.options.indent_size() {
+			self.indent.push(self.options.indent_char);
+		}
+
+		Ok(())
+	}
+
+	fn end_table(&mut self, count: usize) -> Result<()> {
+		self.flush_prefix()?;
+
+		if count > 0 && self.options.trailing_separator() {
+			write!(self.writer, "{}", self.options.separator.as_char())?;
+		}
+
+		self.depth -= 1;
+		self.indent.truncate(
+			self.indent.len() - self.options.indent_size() * self.options.indent_char.len_utf8(),
+		);
+
+		self.writer
+			.write_all(self.options.line_break().as_bytes())?;
+		self.writer.write_all(self.indent.as_bytes())?;
+		self.writer.write_all(b"}")?;
+
+		Ok(())
+	}
+
+	/// Write table entry, returns whether anything was written
+	fn entry<T: ?Sized + Serialize>(
+		&mut self,
+		key: Prefix,
+		value: &T,
+		count: usize,
+		element: bool,
+	) -> Result<bool> {
+		self.prefix.clear();
+
+		if count > 0 {
+			self.prefix.push(self.options.separator.as_char());
+		}
+
+		self.prefix.push_str(self.options.line_break());
+		self.prefix.push_str(&self.indent);
+		self.write_prefix_key(key);
+
+		self.pending = true;
+		self.element = element;
+
+		value.serialize(&mut *self)?;
+
+		// Omitted values leave the prefix unwritten
+		Ok(!std::mem::take(&mut self.pending))
+	}
+
+	fn write_prefix_key(&mut self, key: Prefix) {
+		let assignment = self.options.assignment();
+
+		// Writing into a `String` can't fail
+		let _ = match key {
+			Prefix::Positional => Ok(()),
+			Prefix::Index(index) => write!(self.prefix, "[{index}]{assignment}"),
+			Prefix::Name(key) => write_key(&mut self.prefix, key, &self.options),
+			Prefix::Lua(key) => self.prefix.write_str(key),
+		};
+	}
+
+	/// Begin `{ Variant = ` wrapper used by externally tagged enums
+	fn begin_variant(&mut self, variant: &str) -> Result<()> {
+		self.begin_table()?;
+
+		self.prefix.clear();
+		self.prefix.push_str(self.options.line_break());
+		self.prefix.push_str(&self.indent);
+		self.write_prefix_key(Prefix::Name(variant));
+
+		self.writer.write_all(self.prefix.as_bytes())?;
+
+		Ok(())
+	}
+}
+
+/// Serialize value as a Lua string
+pub fn to_lua_string<T: ?Sized + Serialize>(value: &T) -> Result<String> {
+	let vec = to_lua_vec(value)?;
+	Ok(String::from_utf8(vec).expect("serializer writes UTF-8"))
+}
+
+/// Serialize value as Lua into the IO stream
+pub fn to_lua_writer<W: io::Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<()> {
+	value.serialize(&mut Serializer::new(writer))
+}
+
+/// Serialize value as a Lua byte vector
+pub fn to_lua_vec<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>> {
+	let mut vec = Vec::new();
+	to_lua_writer(&mut vec, value)?;
+	Ok(vec)
+}
+
+impl<'a, W: io::Write> ser::Serializer for &'a mut Serializer<W> {
+	type Ok = ();
+	type Error = Error;
+
+	type SerializeSeq = Compound<'a, W>;
+	type SerializeTuple = Compound<'a, W>;
+	type SerializeTupleStruct = Compound<'a, W>;
+	type SerializeTupleVariant = Compound<'a, W>;
+	type SerializeMap = Compound<'a, W>;
+	type SerializeStruct = Compound<'a, W>;
+	type SerializeStructVariant = Compound<'a, W>;
+
+	fn serialize_bool(self, v: bool) -> Result<()> {
+		self.write(if v { "true" } else { "false" })
+	}
+
+	fn serialize_i8(self, v: i8) -> Result<()> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i16(self, v: i16) -> Result<()> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i32(self, v: i32) -> Result<()> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i64(self, v: i64) -> Result<()> {
+		self.write_number(Number::from(v))
+	}
+
+	fn serialize_i128(self, v: i128) -> Result<()> {
+		match (i64::try_from(v), u64::try_from(v)) {
+			(Ok(v), _) => self.serialize_i64(v),
+			(_, Ok(v)) => self.serialize_u64(v),
+			_ => Err(ser::Error::custom("number out of range")),
+		}
+	}
+
+	fn serialize_u8(self, v: u8) -> Result<()> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u16(self, v: u16) -> Result<()> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u32(self, v: u32) -> Result<()> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u64(self, v: u64) -> Result<()> {
+		self.write_number(Number::from(v))
+	}
+
+	fn serialize_u128(self, v: u128) -> Result<()> {
+		match u64::try_from(v) {
+			Ok(v) => self.serialize_u64(v),
+			Err(_) => Err(ser::Error::custom("number out of range")),
+		}
+	}
+
+	fn serialize_f32(self, v: f32) -> Result<()> {
+		if !v.is_finite() {
+			return self.write_float(v as f64);
+		}
+
+		// Keep the float subtype in Lua 5.3+ and the shortest f32 representation
+		let mut float = v.to_string();
+
+		if !float.contains('.') {
+			float.push_str(".0");
+		}
+
+		self.write(&float)
+	}
+
+	fn serialize_f64(self, v: f64) -> Result<()> {
+		self.write_float(v)
+	}
+
+	fn serialize_char(self, v: char) -> Result<()> {
+		self.serialize_str(v.encode_utf8(&mut [0; 4]))
+	}
+
+	fn serialize_str(self, v: &str) -> Result<()> {
+		let string = quote(v, &self.options);
+		self.write(&string)
+	}
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+		use ser::SerializeSeq;
+
+		let mut seq = self.serialize_seq(Some(v.len()))?;
+
+		for byte in v {
+			seq.serialize_element(byte)?;
+		}
+
+		seq.end()
+	}
+
+	fn serialize_none(self) -> Result<()> {
+		self.serialize_unit()
+	}
+
+	fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<()> {
+		// Nothing to omit at the root
+		match self.options.null_policy {
+			NullPolicy::Omit if self.pending => Ok(()),
+			NullPolicy::Indexed if self.pending && self.element => Ok(()),
+			_ => {
+				let null = null(&self.options)?.to_owned();
+				self.write(&null)
+			}
+		}
+	}
+
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+		self.serialize_unit()
+	}
+
+	fn serialize_unit_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+	) -> Result<()> {
+		self.serialize_str(variant)
+	}
+
+	fn serialize_newtype_struct<T: ?Sized + Serialize>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<()> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: ?Sized + Serialize>(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+		value: &T,
+	) -> Result<()> {
+		self.begin_table()?;
+
+		let written = self.entry(Prefix::Name(variant), value, 0, false);
+		let count = written.map_err(|err| err.nested(variant))? as usize;
+
+		self.end_table(count)
+	}
+
+	fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+		self.begin_table()?;
+		Ok(Compound::new(self, None))
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_struct(
+		self,
+		_name: &'static str,
+		len: usize,
+	) -> Result<Self::SerializeTupleStruct> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeTupleVariant> {
+		self.begin_variant(variant)?;
+		self.begin_table()?;
+		Ok(Compound::new(self, Some(variant)))
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+		self.begin_table()?;
+		Ok(Compound::new(self, None))
+	}
+
+	fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+		self.serialize_map(Some(len))
+	}
+
+	fn serialize_struct_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeStructVariant> {
+		self.begin_variant(variant)?;
+		self.begin_table()?;
+		Ok(Compound::new(self, Some(variant)))
+	}
+}
+
+#[doc(hidden)]
+pub struct Compound<'a, W> {
+	ser: &'a mut Serializer<W>,
+	count: usize,
+	/// Position of the next array element
+	index: usize,
+	/// Whether a null was skipped so following elements need explicit indices
+	holed: bool,
+	key: Option<String>,
+	/// Map key as written in JSON, used for error pointers
+	raw_key: String,
+	/// Variant the table is wrapped in as `{ Variant = ... }`
+	variant: Option<&'static str>,
+}
+
+impl<'a, W: io::Write> Compound<'a, W> {
+	fn new(ser: &'a mut Serializer<W>, variant: Option<&'static str>) -> Self {
+		Self {
+			ser,
+			count: 0,
+			index: 0,
+			holed: false,
+			key: None,
+			raw_key: String::new(),
+			variant,
+		}
+	}
+
+	fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		self.index += 1;
+
+		let key = match self.holed {
+			true => Prefix::Index(self.index),
+			false => Prefix::Positional,
+		};
+
+		let written = self.ser.entry(key, value, self.count, true);
+
+		if written.map_err(|err| self.nested(err, self.index - 1))? {
+			self.count += 1;
+		} else if self.ser.options.null_policy == NullPolicy::Indexed {
+			self.holed = true;
+		}
+
+		Ok(())
+	}
+
+	fn field<T: ?Sized + Serialize>(&mut self, key: Prefix, raw: &str, value: &T) -> Result<()> {
+		let written = self.ser.entry(key, value, self.count, false);
+
+		if written.map_err(|err| self.nested(err, raw))? {
+			self.count += 1;
+		}
+
+		Ok(())
+	}
+
+	fn nested(&self, err: Error, segment: impl std::fmt::Display) -> Error {
+		let err = err.nested(segment);
+
+		match self.variant {
+			Some(variant) => err.nested(variant),
+			None => err,
+		}
+	}
+
+	fn end(mut self) -> Result<()> {
+		if self.holed && self.ser.options.length_field {
+			let length = self.index;
+			self.field(Prefix::Name("n"), "n", &length)?;
+		}
+
+		self.ser.end_table(self.count)?;
+
+		if self.variant.is_some() {
+			self.ser.end_table(1)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl<W: io::Write> ser::SerializeSeq for Compound<'_, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		self.element(value)
+	}
+
+	fn end(self) -> Result<()> {
+		Compound::end(self)
+	}
+}
+
+impl<W: io::Write> ser::SerializeTuple for Compound<'_, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		self.element(value)
+	}
+
+	fn end(self) -> Result<()> {
+		Compound::end(self)
+	}
+}
+
+impl<W: io::Write> ser::SerializeTupleStruct for Compound<'_, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		self.element(value)
+	}
+
+	fn end(self) -> Result<()> {
+		Compound::end(self)
+	}
+}
+
+impl<W: io::Write> ser::SerializeTupleVariant for Compound<'_, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		self.element(value)
+	}
+
+	fn end(self) -> Result<()> {
+		Compound::end(self)
+	}
+}
+
+impl<W: io::Write> ser::SerializeMap for Compound<'_, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+		self.key = Some(key.serialize(KeySerializer {
+			options: &self.ser.options,
+			raw: &mut self.raw_key,
+		})?);
+
+		Ok(())
+	}
+
+	fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		let key = self.key.take().unwrap_or_default();
+		let raw = std::mem::take(&mut self.raw_key);
+
+		self.field(Prefix::Lua(&key), &raw, value)
+	}
+
+	fn end(self) -> Result<()> {
+		Compound::end(self)
+	}
+}
+
+impl<W: io::Write> ser::SerializeStruct for Compound<'_, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_field<T: ?Sized + Serialize>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> Result<()> {
+		self.field(Prefix::Name(key), key, value)
+	}
+
+	fn end(self) -> Result<()> {
+		Compound::end(self)
+	}
+}
+
+impl<W: io::Write> ser::SerializeStructVariant for Compound<'_, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_field<T: ?Sized + Serialize>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> Result<()> {
+		self.field(Prefix::Name(key), key, value)
+	}
+
+	fn end(self) -> Result<()> {
+		Compound::end(self)
+	}
+}
+
+/// Turns map keys into `key = ` or `[key] = ` prefixes
+struct KeySerializer<'a> {
+	options: &'a Options,
+	/// Receives the key as written in JSON
+	raw: &'a mut String,
+}
+
+impl KeySerializer<'_> {
+	fn number(self, number: Number) -> Result<String> {
+		*self.raw = number.to_string();
+
+		let number = self.options.target.number(&number);
+		Ok(format!("[{number}]{}", self.options.assignment()))
+	}
+}
+
+fn key_error() -> Error {
+	ser::Error::custom("table key must be a string, number or boolean")
+}
+
+impl ser::Serializer for KeySerializer<'_> {
+	type Ok = String;
+	type Error = Error;
+
+	type SerializeSeq = Impossible<String, Error>;
+	type SerializeTuple = Impossible<String, Error>;
+	type SerializeTupleStruct = Impossible<String, Error>;
+	type SerializeTupleVariant = Impossible<String, Error>;
+	type SerializeMap = Impossible<String, Error>;
+	type SerializeStruct = Impossible<String, Error>;
+	type SerializeStructVariant = Impossible<String, Error>;
+
+	fn serialize_bool(self, v: bool) -> Result<String> {
+		*self.raw = v.to_string();
+		Ok(format!("[{v}]{}", self.options.assignment()))
+	}
+
+	fn serialize_i8(self, v: i8) -> Result<String> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i16(self, v: i16) -> Result<String> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i32(self, v: i32) -> Result<String> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i64(self, v: i64) -> Result<String> {
+		self.number(Number::from(v))
+	}
+
+	fn serialize_u8(self, v: u8) -> Result<String> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u16(self, v: u16) -> Result<String> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u32(self, v: u32) -> Result<String> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u64(self, v: u64) -> Result<String> {
+		self.number(Number::from(v))
+	}
+
+	fn serialize_f32(self, v: f32) -> Result<String> {
+		self.serialize_f64(v as f64)
+	}
+
+	fn serialize_f64(self, v: f64) -> Result<String> {
+		// NaN can't be a key and infinity has no literal
+		self.number(Number::from_f64(v).ok_or_else(key_error)?)
+	}
+
+	fn serialize_char(self, v: char) -> Result<String> {
+		self.serialize_str(v.encode_utf8(&mut [0; 4]))
+	}
+
+	fn serialize_str(self, v: &str) -> Result<String> {
+		*self.raw = v.to_owned();
+		Ok(get_key(v, self.options))
+	}
+
+	fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+		Err(key_error())
+	}
+
+	fn serialize_none(self) -> Result<String> {
+		Err(key_error())
+	}
+
+	fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<String> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<String> {
+		Err(key_error())
+	}
+
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+		Err(key_error())
+	}
+
+	fn serialize_unit_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		variant: &'static str,
+	) -> Result<String> {
+		self.serialize_str(variant)
+	}
+
+	fn serialize_newtype_struct<T: ?Sized + Serialize>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<String> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: ?Sized + Serialize>(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		_variant: &'static str,
+		_value: &T,
+	) -> Result<String> {
+		Err(key_error())
+	}
+
+	fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+		Err(key_error())
+	}
+
+	fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+		Err(key_error())
+	}
+
+	fn serialize_tuple_struct(
+		self,
+		_name: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeTupleStruct> {
+		Err(key_error())
+	}
+
+	fn serialize_tuple_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		_variant: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeTupleVariant> {
+		Err(key_error())
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+		Err(key_error())
+	}
+
+	fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+		Err(key_error())
+	}
+
+	fn serialize_struct_variant(
+		self,
+		_name: &'static str,
+		_variant_index: u32,
+		_variant: &'static str,
+		_len: usize,
+	) -> Result<Self::SerializeStructVariant> {
+		Err(key_error())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use serde::Serialize;
+	use serde_json::json;
+	use std::collections::BTreeMap;
+
+	use super::{to_lua_string, Serializer};
+	use crate::{parse, parse_with, KeyStyle, Layout, NullPolicy, Options, TrailingComma};
+
+	#[derive(Serialize)]
+	enum Shape {
+		Empty,
+		Circle(f64),
+		Point(i32, i32),
+		Rect { w: u8, h: u8 },
+	}
+
+	#[test]
+	fn matches_parse() {
+		let value = json!({
+			"string": "a\"b\n",
+			"int": 420,
+			"float": 4.2,
+			"bool": true,
+			"null": null,
+			"array": ["string", 12345, false, { "k": "v" }, []],
+			"object": { "key": "value" },
+		});
+
+		let options = Options::new()
+			.key_style(KeyStyle::Identifier)
+			.null_policy(NullPolicy::Omit)
+			.trailing_comma(TrailingComma::Never);
+
+		let mut ser = Serializer::with_options(Vec::new(), &options);
+		value.serialize(&mut ser).unwrap();
+
+		assert_eq!(
+			String::from_utf8(ser.into_inner()).unwrap(),
+			parse_with(&value.to_string(), &options).unwrap()
+		);
+
+		assert_eq!(
+			to_lua_string(&value).unwrap(),
+			parse(&value.to_string()).unwrap()
+		);
+
+		let options = options.layout(Layout::Compact);
+
+		let mut ser = Serializer::with_options(Vec::new(), &options);
+		value.serialize(&mut ser).unwrap();
+
+		assert_eq!(
+			String::from_utf8(ser.into_inner()).unwrap(),
+			parse_with(&value.to_string(), &options).unwrap()
+		);
+	}
+
+	#[test]
+	fn rust_types() {
+		let map = BTreeMap::from([(1, 'a'), (2, 'b')]);
+
+		let value = (
+			map,
+			[Shape::Empty, Shape::Circle(f64::INFINITY)],
+			Shape::Point(1, -2),
+			Shape::Rect { w: 3, h: 4 },
+			Some(0.1f32),
+			None::<u8>,
+		);
+
+		let lua = r#"{
+	{
+		[1] = "a",
+		[2] = "b",
+	},
+	{
+		"Empty",
+		{
+			["Circle"] = 1/0,
+		},
+	},
+	{
+		["Point"] = {
+			1,
+			-2,
+		},
+	},
+	{
+		["Rect"] = {
+			["w"] = 3,
+			["h"] = 4,
+		},
+	},
+	0.1,
+	nil,
+}"#;
+
+		assert_eq!(to_lua_string(&value).unwrap(), lua);
+	}
+}
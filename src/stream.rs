@@ -0,0 +1,385 @@
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde_json::{de::Read, Number};
+use std::{
+	cell::{Cell, RefCell},
+	fmt::{self, Write},
+	io::{self, BufRead, BufReader},
+};
+
+use crate::{
+	emit::{Emitter, Key},
+	error::SNIPPET_RADIUS,
+	limits::{Overflow, State},
+	Annotation, Error, KeyOrder, Layout, NullPolicy, Options, Result,
+};
+
+/// Bytes of the current line kept for syntax errors, enough for any snippet
+const LINE_CAPACITY: usize = 4 * SNIPPET_RADIUS;
+
+/// Whether values can be written as soon as they are parsed
+///
+/// Width layout, sorted keys, indexed nulls and type annotations all depend on whole
+/// tables, so they are converted from a parsed value instead.
+pub(crate) fn is_supported(options: &Options) -> bool {
+	options.annotation == Annotation::None
+		&& options.key_order == KeyOrder::Insertion
+		&& options.null_policy != NullPolicy::Indexed
+		&& !matches!(options.layout, Layout::Width(_))
+}
+
+/// Convert JSON string, writing each value as soon as it is parsed
+pub(crate) fn from_str<'a, W: Write>(
+	json: &str,
+	emitter: &mut Emitter<'a, W>,
+	options: &'a Options,
+) -> Result<()> {
+	if json.len() > options.max_input_size {
+		return Err(Error::InputTooLarge {
+			limit: options.max_input_size,
+		});
+	}
+
+	let deserializer = serde_json::Deserializer::from_str(json);
+
+	convert(deserializer, emitter, options).map_err(|failure| match failure {
+		Failure::Error(err) => err,
+		Failure::Syntax(err) => Error::syntax(err, json),
+	})
+}
+
+/// Convert JSON read from the reader, keeping only open tables and the current line
+pub(crate) fn from_reader<'a, R: io::Read, W: Write>(
+	reader: R,
+	emitter: &mut Emitter<'a, W>,
+	options: &'a Options,
+) -> Result<()> {
+	// One byte past the limit is enough to tell the input is too large
+	let limit = u64::try_from(options.max_input_size).unwrap_or(u64::MAX);
+
+	let mut source = Source {
+		reader: BufReader::new(reader.take(limit.saturating_add(1))),
+		line: Vec::new(),
+		start: 0,
+		read: 0,
+	};
+
+	let deserializer = serde_json::Deserializer::from_reader(&mut source);
+	let result = convert(deserializer, emitter, options);
+
+	// Input past the limit isn't read, so it looks truncated to the parser
+	if source.read > options.max_input_size {
+		return Err(Error::InputTooLarge {
+			limit: options.max_input_size,
+		});
+	}
+
+	result.map_err(|failure| match failure {
+		Failure::Error(err) => err,
+		Failure::Syntax(err) if err.is_io() => Error::Io(err.into()),
+		Failure::Syntax(err) => {
+			source.finish_line();
+
+			let text = String::from_utf8_lossy(&source.line);
+			Error::syntax_in_line(err, &text, source.start)
+		}
+	})
+}
+
+/// Why conversion stopped, syntax errors are quoted by the caller holding the input
+enum Failure {
+	Error(Error),
+	Syntax(serde_json::Error),
+}
+
+fn convert<'de, 'a, R: Read<'de>, W: Write>(
+	mut deserializer: serde_json::Deserializer<R>,
+	emitter: &mut Emitter<'a, W>,
+	options: &'a Options,
+) -> std::result::Result<(), Failure> {
+	// Only open tables are kept, on a stack that grows on the heap
+	deserializer.disable_recursion_limit();
+
+	let state = State::new(options);
+	let mut failure = None;
+
+	let result = emitter.wrap(None, |emitter| {
+		let stream = Stream {
+			state: &state,
+			emitter: RefCell::new(emitter),
+			pending: Cell::new(None),
+			key: RefCell::new(String::new()),
+		};
+
+		let result = stream
+			.deserialize(serde_stacker::Deserializer::new(&mut deserializer))
+			.and_then(|_| deserializer.end());
+
+		result.map_err(|err| match state.error.take() {
+			Some(err) => err,
+			// The emitter only carries crate errors, so the syntax error is kept aside
+			None => {
+				failure = Some(Failure::Syntax(err));
+				Error::Message(String::new())
+			}
+		})
+	});
+
+	result.map_err(|err| failure.unwrap_or(Failure::Error(err)))
+}
+
+/// Visitor writing values into the emitter as they are parsed
+struct Stream<'s, 'e, 'a, W> {
+	state: &'s State<'a>,
+	emitter: RefCell<&'e mut Emitter<'a, W>>,
+	/// Entry whose value is being read, written once the value isn't omitted
+	pending: Cell<Option<Pending>>,
+	/// Key of the pending entry, reused for every key
+	key: RefCell<String>,
+}
+
+#[derive(Clone, Copy)]
+struct Pending {
+	/// Entries written before it
+	count: usize,
+	named: bool,
+}
+
+impl<'a, W: Write> Stream<'_, '_, 'a, W> {
+	/// Write the pending entry followed by the value
+	fn write<T, E: de::Error>(
+		&self,
+		write: impl FnOnce(&mut Emitter<'a, W>) -> Result<T>,
+	) -> std::result::Result<T, E> {
+		let mut emitter = self.emitter.borrow_mut();
+
+		let result = match self.pending.take() {
+			Some(pending) => {
+				let key = self.key.borrow();
+				let key = match pending.named {
+					true => Key::Name(&key),
+					false => Key::Positional,
+				};
+
+				emitter.entry(pending.count, true, key)
+			}
+			None => Ok(()),
+		};
+
+		result
+			.and_then(|_| write(&mut emitter))
+			.map_err(|err| self.state.fail(err))
+	}
+
+	fn scalar<E: de::Error>(
+		&self,
+		write: impl FnOnce(&mut Emitter<'a, W>) -> Result<()>,
+	) -> std::result::Result<(), E> {
+		self.state.node()?;
+
+		if self.state.options.require_table && self.state.depth.get() == 0 {
+			return Err(self.state.fail(Error::NotATable));
+		}
+
+		self.write(write)
+	}
+
+	/// Open table, returning the indentation length to restore once it is closed
+	fn open<E: de::Error>(&self) -> std::result::Result<usize, E> {
+		self.state.node()?;
+		self.state.table()?;
+		self.write(|emitter| emitter.open_table(true))
+	}
+
+	fn close<E: de::Error>(&self, count: usize, outer: usize) -> std::result::Result<(), E> {
+		self.state.depth.set(self.state.depth.get() - 1);
+		self.write(|emitter| emitter.close_table(count, outer, true))
+	}
+
+	/// Read the next value as an entry, returning whether it was written
+	fn entry<E: de::Error>(
+		&self,
+		count: usize,
+		named: bool,
+		read: impl FnOnce() -> std::result::Result<(), E>,
+	) -> std::result::Result<bool, E> {
+		self.pending.set(Some(Pending { count, named }));
+
+		let result = read();
+		let omitted = self.pending.take().is_some();
+
+		result.map(|_| !omitted)
+	}
+}
+
+impl<'de, W: Write> DeserializeSeed<'de> for &Stream<'_, '_, '_, W> {
+	type Value = ();
+
+	fn deserialize<D: de::Deserializer<'de>>(
+		self,
+		deserializer: D,
+	) -> std::result::Result<(), D::Error> {
+		deserializer.deserialize_any(self)
+	}
+}
+
+impl<'de, W: Write> Visitor<'de> for &Stream<'_, '_, '_, W> {
+	type Value = ();
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("any valid JSON value")
+	}
+
+	fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<(), E> {
+		self.scalar(|emitter| emitter.boolean(v))
+	}
+
+	fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<(), E> {
+		self.scalar(|emitter| emitter.number(&v.into()))
+	}
+
+	fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<(), E> {
+		self.scalar(|emitter| emitter.number(&v.into()))
+	}
+
+	fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<(), E> {
+		self.scalar(|emitter| match Number::from_f64(v) {
+			Some(number) => emitter.number(&number),
+			None => emitter.null(),
+		})
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<(), E> {
+		self.state.string(v)?;
+		self.scalar(|emitter| emitter.string(v))
+	}
+
+	fn visit_unit<E: de::Error>(self) -> std::result::Result<(), E> {
+		if self.state.options.null_policy == NullPolicy::Omit && self.state.depth.get() > 0 {
+			// Leaves the entry pending so it is never written
+			return self.state.node();
+		}
+
+		self.scalar(|emitter| emitter.null())
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+		let outer = self.open()?;
+
+		let mut length = 0;
+		let mut count = 0;
+
+		loop {
+			if length == self.state.options.max_array_length {
+				// Fails as soon as there is another element, without reading it
+				seq.next_element_seed(Overflow(self.state))?;
+				break;
+			}
+
+			let mut end = false;
+
+			let written = self.entry(count, false, || {
+				end = seq
+					.next_element_seed(self)
+					.map_err(self.state.nested(length))?
+					.is_none();
+
+				Ok(())
+			})?;
+
+			if end {
+				break;
+			}
+
+			length += 1;
+			count += usize::from(written);
+		}
+
+		self.close(count, outer)
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+		let outer = self.open()?;
+
+		let mut keys = 0;
+		let mut count = 0;
+
+		while let Some(key) = map.next_key::<String>()? {
+			self.state.string(&key)?;
+
+			if keys == self.state.options.max_object_keys {
+				return Err(self.state.fail(Error::TooManyKeys {
+					pointer: String::new(),
+					limit: self.state.options.max_object_keys,
+				}));
+			}
+
+			{
+				let mut pending = self.key.borrow_mut();
+				pending.clear();
+				pending.push_str(&key);
+			}
+
+			let written = self.entry(count, true, || {
+				map.next_value_seed(self).map_err(self.state.nested(&key))
+			})?;
+
+			keys += 1;
+			count += usize::from(written);
+		}
+
+		self.close(count, outer)
+	}
+}
+
+/// Reader that keeps the end of the current line so syntax errors can quote it
+struct Source<R> {
+	reader: R,
+	line: Vec<u8>,
+	/// Bytes dropped from the beginning of the line
+	start: usize,
+	/// Bytes read in total
+	read: usize,
+}
+
+impl<R: BufRead> Source<R> {
+	/// Read the rest of the line the parser stopped on, as far as a snippet shows it
+	fn finish_line(&mut self) {
+		let mut byte = [0];
+
+		while self.line.len() < 2 * LINE_CAPACITY {
+			match self.reader.read(&mut byte) {
+				Ok(1) if byte[0] != b'\n' => self.line.push(byte[0]),
+				_ => break,
+			}
+		}
+	}
+}
+
+impl<R: BufRead> io::Read for Source<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let read = self.reader.read(buf)?;
+
+		for &byte in &buf[..read] {
+			if byte == b'\n' {
+				self.line.clear();
+				self.start = 0;
+				continue;
+			}
+
+			self.line.push(byte);
+
+			if self.line.len() == 2 * LINE_CAPACITY {
+				// Cut on a character boundary so the rest stays valid UTF-8
+				let cut = (LINE_CAPACITY..self.line.len())
+					.find(|&i| self.line[i] & 0xC0 != 0x80)
+					.unwrap_or(self.line.len());
+
+				self.line.drain(..cut);
+				self.start += cut;
+			}
+		}
+
+		self.read += read;
+		Ok(read)
+	}
+}
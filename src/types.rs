@@ -0,0 +1,1064 @@
+//! Infer Lua type definitions from JSON data
+//!
+//! ```rust
+//! use json2lua::types::Type;
+//! use serde_json::json;
+//!
+//! let items = json!([
+//! 	{ "id": 1, "tag": "sharp" },
+//! 	{ "id": 2.5 },
+//! ]);
+//!
+//! let luau = "{{\n\tid: number,\n\ttag: string?,\n}}";
+//!
+//! assert_eq!(Type::of(&items).to_luau(), luau);
+//! ```
+//!
+//! Types of many documents are merged with an [`Inferrer`]:
+//!
+//! ```rust
+//! use json2lua::{types::{Dialect, Inferrer}, Options};
+//!
+//! let mut inferrer = Inferrer::new().literals(2);
+//!
+//! inferrer.add_str(r#"{ "name": "Sword", "kind": "weapon" }"#).unwrap();
+//! inferrer.add_str(r#"{ "name": "Plate", "kind": "armor", "weight": 4 }"#).unwrap();
+//! inferrer.add_str(r#"{ "name": "Axe", "kind": "weapon" }"#).unwrap();
+//!
+//! let luau = r#"export type Item = {
+//! 	name: string,
+//! 	kind: "weapon" | "armor",
+//! 	weight: number?,
+//! }"#;
+//!
+//! let item = inferrer.ty().declare("Item", Dialect::Luau, &Options::new());
+//! assert_eq!(item.unwrap(), luau);
+//! ```
+
+use serde_json::Value;
+use std::{
+	collections::{HashMap, HashSet},
+	fmt::{self, Write},
+	mem,
+};
+
+use crate::{
+	escape::{quote, write_quoted},
+	Error, LuaTarget, Options, Result,
+};
+
+/// Type of a JSON value, or of many values merged together
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Type {
+	/// No value seen yet, e.g. elements of an empty array
+	#[default]
+	Unknown,
+	Nil,
+	Boolean,
+	Integer,
+	Number,
+	String,
+	/// Strings that were only ever one of the values, see [`Inferrer::literals`]
+	Literals(Vec<String>),
+	/// Array with elements of the given type
+	Array(Box<Type>),
+	/// Object with fields in the order they were first seen
+	Record(Vec<Field>),
+	/// Values of different kinds, never nested and never containing [`Type::Unknown`]
+	Union(Vec<Type>),
+	/// Type declared elsewhere under the name
+	Named(String),
+}
+
+/// Field of a [`Type::Record`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+	pub name: String,
+	pub ty: Type,
+	/// Whether the field is missing from some of the objects
+	pub optional: bool,
+}
+
+/// Syntax of type declarations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+	/// Luau `export type` alias
+	Luau,
+	/// Teal `record`, `enum` and `type` declarations
+	Teal,
+	/// `---@class` and `---@field` comments of lua-language-server
+	LuaLS,
+}
+
+impl Dialect {
+	/// Whether the name refers to a built-in type, which declarations can't shadow
+	pub(crate) fn is_builtin(self, name: &str) -> bool {
+		let builtins: &[&str] = match self {
+			Dialect::Luau => &[
+				"any", "boolean", "buffer", "never", "nil", "number", "string", "table", "thread",
+				"typeof", "unknown", "userdata", "vector",
+			],
+			Dialect::Teal => &[
+				"any", "boolean", "integer", "nil", "number", "string", "thread",
+			],
+			Dialect::LuaLS => &[
+				"any",
+				"boolean",
+				"function",
+				"integer",
+				"lightuserdata",
+				"nil",
+				"number",
+				"string",
+				"table",
+				"thread",
+				"unknown",
+				"userdata",
+			],
+		};
+
+		builtins.contains(&name)
+	}
+}
+
+impl Type {
+	/// Infer type of the value
+	pub fn of(value: &Value) -> Type {
+		Type::infer(value, 0)
+	}
+
+	/// Combine two types into one that describes values of both
+	///
+	/// Records merge field by field, fields missing from either side become optional.
+	pub fn merge(self, other: Type) -> Type {
+		self.merge_limited(other, usize::MAX)
+	}
+
+	/// Whether `nil` is one of the values
+	pub fn is_nilable(&self) -> bool {
+		match self {
+			Type::Nil => true,
+			Type::Union(members) => members.contains(&Type::Nil),
+			_ => false,
+		}
+	}
+
+	/// Render as a Luau type using default [`Options`]
+	pub fn to_luau(&self) -> String {
+		let mut luau = String::new();
+		let _ = Writer::new(&mut luau, &Options::default()).luau(self);
+		luau
+	}
+
+	/// Declare the type under the name, names are checked with [`LuaTarget::is_identifier`]
+	///
+	/// Teal and LuaLS can't nest records, so every nested record is declared on its own
+	/// and named after its key path, e.g. `ItemStats` for the `stats` field of `Item`.
+	/// Teal declares literal unions as `enum` the same way. Declarations are local, so a
+	/// Teal record is followed by `return NAME` to make the chunk usable as a `.d.tl`
+	/// file. Other Teal roots aren't values and stay local, to be pasted into a module.
+	///
+	/// ```rust
+	/// use json2lua::{types::{Dialect, Type}, Options};
+	/// use serde_json::json;
+	///
+	/// let item = json!({ "name": "Sword", "stats": { "damage": 7 } });
+	///
+	/// let luals = "\
+	/// ---@class ItemStats
+	/// ---@field damage integer
+	///
+	/// ---@class Item
+	/// ---@field name string
+	/// ---@field stats ItemStats";
+	///
+	/// let declaration = Type::of(&item).declare("Item", Dialect::LuaLS, &Options::new());
+	/// assert_eq!(declaration.unwrap(), luals);
+	/// ```
+	pub fn declare(&self, name: &str, dialect: Dialect, options: &Options) -> Result<String> {
+		let mut lua = String::new();
+		let mut writer = Writer::new(&mut lua, options);
+
+		writer.declare(self, name, dialect)?;
+
+		if dialect == Dialect::Teal && matches!(self, Type::Record(_)) {
+			writer.gap()?;
+			write!(writer.writer, "return {name}")?;
+		}
+
+		Ok(lua)
+	}
+
+	/// Union of the types as they are, unlike [`Type::merge`] which combines types of the
+	/// same kind
+	pub(crate) fn union(types: impl IntoIterator<Item = Type>) -> Type {
+		let mut members = Vec::new();
+
+		for member in types.into_iter().flat_map(Type::into_members) {
+			if !members.contains(&member) {
+				members.push(member);
+			}
+		}
+
+		match members.len() {
+			0 => Type::Unknown,
+			1 => members.swap_remove(0),
+			_ => Type::Union(members),
+		}
+	}
+
+	/// Infer type, keeping strings as literals when `literals` is not `0`
+	fn infer(value: &Value, literals: usize) -> Type {
+		match value {
+			Value::Null => Type::Nil,
+			Value::Bool(_) => Type::Boolean,
+			Value::Number(n) if n.is_f64() => Type::Number,
+			Value::Number(_) => Type::Integer,
+			Value::String(string) if literals > 0 => Type::Literals(vec![string.clone()]),
+			Value::String(_) => Type::String,
+			Value::Array(values) => {
+				let element = values
+					.iter()
+					.map(|value| Type::infer(value, literals))
+					.fold(Type::Unknown, |a, b| a.merge_limited(b, literals));
+
+				Type::Array(Box::new(element))
+			}
+			Value::Object(map) => Type::Record(
+				map.iter()
+					.map(|(name, value)| Field {
+						name: name.clone(),
+						ty: Type::infer(value, literals),
+						optional: false,
+					})
+					.collect(),
+			),
+		}
+	}
+
+	/// Merge, turning literal unions with more than `literals` values into plain strings
+	fn merge_limited(self, other: Type, literals: usize) -> Type {
+		let mut members = self.into_members();
+
+		for ty in other.into_members() {
+			match members.iter().position(|member| member.is_like(&ty)) {
+				Some(i) => {
+					let member = mem::take(&mut members[i]);
+					members[i] = member.combine(ty, literals);
+				}
+				None => members.push(ty),
+			}
+		}
+
+		match members.len() {
+			0 => Type::Unknown,
+			1 => members.swap_remove(0),
+			_ => Type::Union(members),
+		}
+	}
+
+	fn into_members(self) -> Vec<Type> {
+		match self {
+			Type::Unknown => Vec::new(),
+			Type::Union(members) => members,
+			ty => vec![ty],
+		}
+	}
+
+	/// Types of the same kind merge into one instead of forming a union
+	fn is_like(&self, other: &Type) -> bool {
+		fn kind(ty: &Type) -> u8 {
+			match ty {
+				Type::Unknown => 0,
+				Type::Nil => 1,
+				Type::Boolean => 2,
+				Type::Integer | Type::Number => 3,
+				Type::String | Type::Literals(_) => 4,
+				Type::Array(_) => 5,
+				Type::Record(_) => 6,
+				Type::Union(_) => 7,
+				Type::Named(_) => 8,
+			}
+		}
+
+		match (self, other) {
+			(Type::Named(a), Type::Named(b)) => a == b,
+			_ => kind(self) == kind(other),
+		}
+	}
+
+	fn combine(self, other: Type, literals: usize) -> Type {
+		match (self, other) {
+			(Type::Integer, Type::Integer) => Type::Integer,
+			(Type::Integer | Type::Number, Type::Integer | Type::Number) => Type::Number,
+			(Type::Literals(mut values), Type::Literals(other)) => {
+				for value in other {
+					if !values.contains(&value) {
+						values.push(value);
+					}
+				}
+
+				match values.len() > literals {
+					true => Type::String,
+					false => Type::Literals(values),
+				}
+			}
+			(Type::String | Type::Literals(_), Type::String | Type::Literals(_)) => Type::String,
+			(Type::Array(a), Type::Array(b)) => {
+				Type::Array(Box::new(a.merge_limited(*b, literals)))
+			}
+			(Type::Record(a), Type::Record(b)) => Type::Record(merge_fields(a, b, literals)),
+			(ty, _) => ty,
+		}
+	}
+
+	/// Number of alternatives other than `nil`
+	fn alternatives(&self) -> usize {
+		match self {
+			Type::Nil => 0,
+			Type::Literals(values) => values.len(),
+			Type::Union(members) => members.iter().map(Type::alternatives).sum(),
+			_ => 1,
+		}
+	}
+}
+
+fn merge_fields(mut fields: Vec<Field>, other: Vec<Field>, literals: usize) -> Vec<Field> {
+	let positions: HashMap<String, usize> = fields
+		.iter()
+		.enumerate()
+		.map(|(i, field)| (field.name.clone(), i))
+		.collect();
+
+	let mut seen = vec![false; fields.len()];
+
+	for theirs in other {
+		match positions.get(&theirs.name) {
+			Some(&i) => {
+				let field = &mut fields[i];
+
+				field.ty = mem::take(&mut field.ty).merge_limited(theirs.ty, literals);
+				field.optional |= theirs.optional;
+				seen[i] = true;
+			}
+			None => fields.push(Field {
+				optional: true,
+				..theirs
+			}),
+		}
+	}
+
+	for (field, seen) in fields.iter_mut().zip(seen) {
+		field.optional |= !seen;
+	}
+
+	fields
+}
+
+/// Merges types of many JSON documents into one
+///
+/// Fields missing from any of the documents become optional.
+#[derive(Debug, Clone, Default)]
+pub struct Inferrer {
+	ty: Type,
+	literals: usize,
+}
+
+impl Inferrer {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Keep strings as a union of literals while they have at most `limit` distinct
+	/// values, `0` by default so every string is a plain `string`
+	pub fn literals(mut self, limit: usize) -> Self {
+		self.literals = limit;
+		self
+	}
+
+	/// Merge type of the document
+	pub fn add(&mut self, value: &Value) {
+		let ty = Type::infer(value, self.literals);
+		self.ty = mem::take(&mut self.ty).merge_limited(ty, self.literals);
+	}
+
+	/// Parse JSON document and merge its type
+	pub fn add_str(&mut self, json: &str) -> Result<()> {
+		let value = serde_json::from_str(json).map_err(|err| Error::syntax(err, json))?;
+		self.add(&value);
+		Ok(())
+	}
+
+	/// Type of every document added so far
+	pub fn ty(&self) -> &Type {
+		&self.ty
+	}
+
+	pub fn finish(self) -> Type {
+		self.ty
+	}
+}
+
+/// Type declared on its own, see [`declarations`]
+struct Declaration {
+	name: String,
+	ty: Type,
+}
+
+/// Split nested records, and literal unions with `enums`, into declarations named after
+/// their key path, in the order they can be declared in
+fn declarations(ty: &Type, name: &str, enums: bool) -> Vec<Declaration> {
+	let mut names = Names {
+		used: HashSet::from([name.to_owned()]),
+		enums,
+		declarations: Vec::new(),
+	};
+
+	let ty = match ty.clone() {
+		Type::Record(fields) => Type::Record(names.fields(fields, name)),
+		ty => names.extract(ty, &format!("{name}Element")),
+	};
+
+	names.declarations.push(Declaration {
+		name: name.to_owned(),
+		ty,
+	});
+
+	names.declarations
+}
+
+struct Names {
+	used: HashSet<String>,
+	enums: bool,
+	declarations: Vec<Declaration>,
+}
+
+impl Names {
+	/// Replace types that need declaring with references to them
+	fn extract(&mut self, ty: Type, path: &str) -> Type {
+		match ty {
+			Type::Array(element) => Type::Array(Box::new(self.extract(*element, path))),
+			Type::Union(members) => Type::Union(
+				members
+					.into_iter()
+					.map(|ty| self.extract(ty, path))
+					.collect(),
+			),
+			Type::Record(fields) if !fields.is_empty() => {
+				let fields = self.fields(fields, path);
+				self.declare(path, Type::Record(fields))
+			}
+			Type::Literals(values) if self.enums => self.declare(path, Type::Literals(values)),
+			ty => ty,
+		}
+	}
+
+	fn fields(&mut self, fields: Vec<Field>, path: &str) -> Vec<Field> {
+		fields
+			.into_iter()
+			.map(|field| Field {
+				ty: self.extract(field.ty, &format!("{path}{}", pascal_case(&field.name))),
+				..field
+			})
+			.collect()
+	}
+
+	fn declare(&mut self, path: &str, ty: Type) -> Type {
+		let mut name = path.to_owned();
+		let mut suffix = 1;
+
+		// Different keys like `a_b` and `aB` can end up with the same name
+		while !self.used.insert(name.clone()) {
+			suffix += 1;
+			name = format!("{path}{suffix}");
+		}
+
+		self.declarations.push(Declaration {
+			name: name.clone(),
+			ty,
+		});
+
+		Type::Named(name)
+	}
+}
+
+/// Turn key into a part of a type name, `weapon_stats` into `WeaponStats`
+pub(crate) fn pascal_case(key: &str) -> String {
+	key.split(|char: char| !char.is_ascii_alphanumeric())
+		.flat_map(|word| {
+			let mut chars = word.chars();
+			let first = chars.next().map(|char| char.to_ascii_uppercase());
+
+			first.into_iter().chain(chars)
+		})
+		.collect()
+}
+
+/// Renders types using the indentation and newline settings of [`Options`]
+pub(crate) struct Writer<'a, W> {
+	writer: W,
+	options: &'a Options,
+	indent: String,
+}
+
+impl<'a, W: Write> Writer<'a, W> {
+	pub(crate) fn new(writer: W, options: &'a Options) -> Self {
+		Self {
+			writer,
+			options,
+			indent: String::new(),
+		}
+	}
+
+	/// Write declaration of the type, see [`Type::declare`]
+	pub(crate) fn declare(&mut self, ty: &Type, name: &str, dialect: Dialect) -> Result<()> {
+		if !self.options.target.is_identifier(name) || dialect.is_builtin(name) {
+			return Err(Error::InvalidName(name.to_owned()));
+		}
+
+		match dialect {
+			Dialect::Luau => {
+				write!(self.writer, "export type {name} = ")?;
+				self.luau(ty)?;
+			}
+			Dialect::Teal => self.teal_declarations(&declarations(ty, name, true))?,
+			Dialect::LuaLS => self.luals_declarations(&declarations(ty, name, false))?,
+		}
+
+		Ok(())
+	}
+
+	/// Write type in Luau syntax
+	pub(crate) fn luau(&mut self, ty: &Type) -> fmt::Result {
+		match ty {
+			Type::Unknown => self.writer.write_str("any"),
+			Type::Nil => self.writer.write_str("nil"),
+			Type::Boolean => self.writer.write_str("boolean"),
+			Type::Integer | Type::Number => self.writer.write_str("number"),
+			Type::String => self.writer.write_str("string"),
+			Type::Literals(values) => self.literals(values, " | "),
+			Type::Named(name) => self.writer.write_str(name),
+			Type::Array(element) => {
+				self.writer.write_char('{')?;
+				self.luau(element)?;
+				self.writer.write_char('}')
+			}
+			Type::Record(fields) => self.luau_record(fields),
+			Type::Union(members) => self.luau_union(members, ty.is_nilable()),
+		}
+	}
+
+	/// Write members other than `nil` as alternatives, optional when `nilable`
+	fn luau_union(&mut self, members: &[Type], nilable: bool) -> fmt::Result {
+		let members: Vec<&Type> = members.iter().filter(|ty| **ty != Type::Nil).collect();
+		let alternatives: usize = members.iter().map(|ty| ty.alternatives()).sum();
+
+		// Optional unions need parentheses around them
+		let parenthesized = nilable && alternatives > 1;
+
+		if parenthesized {
+			self.writer.write_char('(')?;
+		}
+
+		for (i, member) in members.iter().enumerate() {
+			if i > 0 {
+				self.writer.write_str(" | ")?;
+			}
+
+			self.luau(member)?;
+		}
+
+		if parenthesized {
+			self.writer.write_char(')')?;
+		}
+
+		if nilable {
+			self.writer.write_char('?')?;
+		}
+
+		Ok(())
+	}
+
+	fn luau_record(&mut self, fields: &[Field]) -> fmt::Result {
+		let options = self.options;
+		let newline = options.line_break();
+		let outer = self.indent.len();
+
+		if fields.is_empty() {
+			return self.writer.write_str("{}");
+		}
+
+		self.writer.write_char('{')?;
+		self.push_indent();
+
+		for (i, field) in fields.iter().enumerate() {
+			if i > 0 {
+				self.writer.write_char(',')?;
+			}
+
+			self.writer.write_str(newline)?;
+			self.writer.write_str(&self.indent)?;
+			self.field_name(&field.name, LuaTarget::Luau)?;
+			self.writer.write_str(": ")?;
+
+			match &field.ty {
+				// Fields that are always null are only ever missing
+				Type::Nil => self.writer.write_str("nil")?,
+				Type::Union(members) => {
+					self.luau_union(members, field.optional || field.ty.is_nilable())?
+				}
+				ty => self.luau_union(std::slice::from_ref(ty), field.optional)?,
+			}
+		}
+
+		if options.trailing_separator() {
+			self.writer.write_char(',')?;
+		}
+
+		self.indent.truncate(outer);
+
+		self.writer.write_str(newline)?;
+		self.writer.write_str(&self.indent)?;
+		self.writer.write_char('}')
+	}
+
+	fn teal_declarations(&mut self, declarations: &[Declaration]) -> fmt::Result {
+		for (i, Declaration { name, ty }) in declarations.iter().enumerate() {
+			if i > 0 {
+				self.gap()?;
+			}
+
+			match ty {
+				Type::Record(fields) => {
+					write!(self.writer, "local record {name}")?;
+					self.push_indent();
+
+					for field in fields {
+						self.line()?;
+						self.field_name(&field.name, LuaTarget::Lua54)?;
+						self.writer.write_str(": ")?;
+						self.teal(&field.ty)?;
+					}
+				}
+				Type::Literals(values) => {
+					write!(self.writer, "local enum {name}")?;
+					self.push_indent();
+
+					for value in values {
+						self.line()?;
+						write_quoted(&mut self.writer, value, self.options)?;
+					}
+				}
+				ty => {
+					write!(self.writer, "local type {name} = ")?;
+					self.teal(ty)?;
+					continue;
+				}
+			}
+
+			self.indent.clear();
+			self.line()?;
+			self.writer.write_str("end")?;
+		}
+
+		Ok(())
+	}
+
+	/// Write type in Teal syntax, where every type can be `nil`
+	fn teal(&mut self, ty: &Type) -> fmt::Result {
+		match ty {
+			Type::Unknown | Type::Nil => self.writer.write_str("any"),
+			Type::Boolean => self.writer.write_str("boolean"),
+			Type::Integer => self.writer.write_str("integer"),
+			Type::Number => self.writer.write_str("number"),
+			Type::String | Type::Literals(_) => self.writer.write_str("string"),
+			Type::Named(name) => self.writer.write_str(name),
+			Type::Array(element) => {
+				self.writer.write_char('{')?;
+				self.teal(element)?;
+				self.writer.write_char('}')
+			}
+			Type::Record(_) => self.writer.write_str("{string: any}"),
+			Type::Union(members) => {
+				let members: Vec<&Type> = members.iter().filter(|ty| **ty != Type::Nil).collect();
+
+				// Teal can't tell tables apart at runtime, so unions hold one of them at most
+				let tables = members
+					.iter()
+					.filter(|ty| matches!(ty, Type::Array(_) | Type::Record(_) | Type::Named(_)))
+					.count();
+
+				if tables > 1 {
+					return self.writer.write_str("any");
+				}
+
+				for (i, member) in members.iter().enumerate() {
+					if i > 0 {
+						self.writer.write_str(" | ")?;
+					}
+
+					self.teal(member)?;
+				}
+
+				Ok(())
+			}
+		}
+	}
+
+	fn luals_declarations(&mut self, declarations: &[Declaration]) -> fmt::Result {
+		// Annotations are comments, so they can't share a line even when compact
+		let newline = self.options.newline.as_str();
+
+		for (i, Declaration { name, ty }) in declarations.iter().enumerate() {
+			if i > 0 {
+				self.writer.write_str(newline)?;
+
+				if !self.options.is_compact() {
+					self.writer.write_str(newline)?;
+				}
+			}
+
+			let Type::Record(fields) = ty else {
+				write!(self.writer, "---@alias {name} ")?;
+				self.luals(ty)?;
+				continue;
+			};
+
+			write!(self.writer, "---@class {name}")?;
+
+			for field in fields {
+				self.writer.write_str(newline)?;
+				self.writer.write_str("---@field ")?;
+				self.field_name(&field.name, LuaTarget::Lua54)?;
+
+				match &field.ty {
+					Type::Nil => self.writer.write_str(" nil")?,
+					Type::Union(members) if field.ty.is_nilable() => {
+						self.writer.write_str("? ")?;
+						self.luals_union(members)?;
+					}
+					ty => {
+						if field.optional {
+							self.writer.write_char('?')?;
+						}
+
+						self.writer.write_char(' ')?;
+						self.luals(ty)?;
+					}
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Write type in the syntax of lua-language-server annotations
+	fn luals(&mut self, ty: &Type) -> fmt::Result {
+		match ty {
+			Type::Unknown => self.writer.write_str("any"),
+			Type::Nil => self.writer.write_str("nil"),
+			Type::Boolean => self.writer.write_str("boolean"),
+			Type::Integer => self.writer.write_str("integer"),
+			Type::Number => self.writer.write_str("number"),
+			Type::String => self.writer.write_str("string"),
+			Type::Literals(values) => self.literals(values, "|"),
+			Type::Named(name) => self.writer.write_str(name),
+			Type::Array(element) => {
+				let parenthesized = element.alternatives() > 1 || element.is_nilable();
+
+				if parenthesized {
+					self.writer.write_char('(')?;
+				}
+
+				self.luals(element)?;
+
+				if parenthesized {
+					self.writer.write_char(')')?;
+				}
+
+				self.writer.write_str("[]")
+			}
+			Type::Record(_) => self.writer.write_str("table"),
+			Type::Union(members) => {
+				self.luals_union(members)?;
+
+				match (ty.is_nilable(), ty.alternatives()) {
+					(false, _) => Ok(()),
+					(true, 1) => self.writer.write_char('?'),
+					(true, _) => self.writer.write_str("|nil"),
+				}
+			}
+		}
+	}
+
+	/// Write members other than `nil` as alternatives
+	fn luals_union(&mut self, members: &[Type]) -> fmt::Result {
+		let members = members.iter().filter(|ty| **ty != Type::Nil);
+
+		for (i, member) in members.enumerate() {
+			if i > 0 {
+				self.writer.write_char('|')?;
+			}
+
+			self.luals(member)?;
+		}
+
+		Ok(())
+	}
+
+	fn literals(&mut self, values: &[String], separator: &str) -> fmt::Result {
+		for (i, value) in values.iter().enumerate() {
+			if i > 0 {
+				self.writer.write_str(separator)?;
+			}
+
+			write_quoted(&mut self.writer, value, self.options)?;
+		}
+
+		Ok(())
+	}
+
+	/// Write field name, quoted in brackets unless it is an identifier of the target
+	fn field_name(&mut self, name: &str, target: LuaTarget) -> fmt::Result {
+		if target.is_identifier(name) {
+			self.writer.write_str(name)
+		} else {
+			write!(self.writer, "[{}]", quote(name, self.options))
+		}
+	}
+
+	fn push_indent(&mut self) {
+		for _ in 0..self.options.indent_size() {
+			self.indent.push(self.options.indent_char);
+		}
+	}
+
+	/// Start a new indented line, or just separate from the previous word when compact
+	fn line(&mut self) -> fmt::Result {
+		match self.options.is_compact() {
+			true => self.writer.write_char(' '),
+			false => {
+				self.writer.write_str(self.options.newline.as_str())?;
+				self.writer.write_str(&self.indent)
+			}
+		}
+	}
+
+	/// Separate declarations with an empty line
+	fn gap(&mut self) -> fmt::Result {
+		self.writer.write_str(self.options.line_break())?;
+		self.line()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Dialect, Field, Inferrer, Type};
+	use crate::{Error, Options};
+	use serde_json::json;
+
+	#[test]
+	fn inference() {
+		let value = json!({
+			"name": "sword",
+			"stats": [
+				{ "level": 1, "bonus": null },
+				{ "level": 2.5, "tags": ["a"], "bonus": 3 },
+				{ "level": 3, "tags": [] },
+			],
+			"mixed": [1, "two", true, [], null],
+			"empty": [],
+			"weird key": {},
+		});
+
+		let field = |name: &str, ty, optional| Field {
+			name: name.to_owned(),
+			ty,
+			optional,
+		};
+
+		let stats = Type::Record(vec![
+			field("level", Type::Number, false),
+			field("bonus", Type::Union(vec![Type::Nil, Type::Integer]), true),
+			field("tags", Type::Array(Box::new(Type::String)), true),
+		]);
+
+		let mixed = Type::Union(vec![
+			Type::Integer,
+			Type::String,
+			Type::Boolean,
+			Type::Array(Box::new(Type::Unknown)),
+			Type::Nil,
+		]);
+
+		assert_eq!(
+			Type::of(&value),
+			Type::Record(vec![
+				field("name", Type::String, false),
+				field("stats", Type::Array(Box::new(stats)), false),
+				field("mixed", Type::Array(Box::new(mixed)), false),
+				field("empty", Type::Array(Box::new(Type::Unknown)), false),
+				field("weird key", Type::Record(Vec::new()), false),
+			])
+		);
+
+		let luau = r#"{
+	name: string,
+	stats: {{
+		level: number,
+		bonus: number?,
+		tags: {string}?,
+	}},
+	mixed: {(number | string | boolean | {any})?},
+	empty: {any},
+	["weird key"]: {},
+}"#;
+
+		assert_eq!(Type::of(&value).to_luau(), luau);
+	}
+
+	#[test]
+	fn merge() {
+		let a = Type::of(&json!({ "a": 1, "b": "x" }));
+		let b = Type::of(&json!({ "b": 2, "c": null }));
+
+		assert_eq!(
+			a.merge(b).to_luau(),
+			"{\n\ta: number?,\n\tb: string | number,\n\tc: nil,\n}"
+		);
+
+		assert_eq!(Type::Unknown.merge(Type::Nil), Type::Nil);
+		assert_eq!(Type::Integer.merge(Type::Number), Type::Number);
+		assert_eq!(
+			Type::Union(vec![Type::String, Type::Nil])
+				.merge(Type::Union(vec![Type::Nil, Type::Boolean])),
+			Type::Union(vec![Type::String, Type::Nil, Type::Boolean])
+		);
+	}
+
+	#[test]
+	fn inferrer() {
+		let mut inferrer = Inferrer::new().literals(2);
+
+		inferrer.add(&json!({ "kind": "a", "tags": ["x"] }));
+		inferrer.add(&json!({ "kind": "b", "tags": ["y", "z"], "extra": null }));
+
+		assert_eq!(
+			inferrer.ty().to_luau(),
+			"{\n\tkind: \"a\" | \"b\",\n\ttags: {string},\n\textra: nil,\n}"
+		);
+
+		assert!(inferrer.add_str("{").is_err());
+		inferrer.add_str(r#"{ "kind": "c" }"#).unwrap();
+
+		assert_eq!(
+			inferrer.finish().to_luau(),
+			"{\n\tkind: string,\n\ttags: {string}?,\n\textra: nil,\n}"
+		);
+	}
+
+	#[test]
+	fn declarations() {
+		let mut inferrer = Inferrer::new().literals(3);
+
+		inferrer.add(&json!([
+			{
+				"id": 1,
+				"kind": "weapon",
+				"weapon_stats": { "damage": 1.5, "range": null },
+				"weaponStats": {},
+				"drops": [{ "chance": 0.5 }],
+				"end": true,
+			},
+			{
+				"id": 2,
+				"kind": "armor",
+				"weaponStats": { "x": 1 },
+				"drops": [],
+				"end": [1, 2.5],
+			},
+		]));
+
+		let ty = inferrer.finish();
+		let options = Options::new();
+
+		let teal = r#"local enum RootElementKind
+	"weapon"
+	"armor"
+end
+
+local record RootElementWeaponStats
+	damage: number
+	range: any
+end
+
+local record RootElementWeaponStats2
+	x: integer
+end
+
+local record RootElementDrops
+	chance: number
+end
+
+local record RootElement
+	id: integer
+	kind: RootElementKind
+	weapon_stats: RootElementWeaponStats
+	weaponStats: RootElementWeaponStats2
+	drops: {RootElementDrops}
+	["end"]: boolean | {number}
+end
+
+local type Root = {RootElement}"#;
+
+		assert_eq!(ty.declare("Root", Dialect::Teal, &options).unwrap(), teal);
+
+		let luals = r#"---@class RootElementWeaponStats
+---@field damage number
+---@field range nil
+
+---@class RootElementWeaponStats2
+---@field x? integer
+
+---@class RootElementDrops
+---@field chance number
+
+---@class RootElement
+---@field id integer
+---@field kind "weapon"|"armor"
+---@field weapon_stats? RootElementWeaponStats
+---@field weaponStats RootElementWeaponStats2
+---@field drops RootElementDrops[]
+---@field ["end"] boolean|number[]
+
+---@alias Root RootElement[]"#;
+
+		assert_eq!(ty.declare("Root", Dialect::LuaLS, &options).unwrap(), luals);
+
+		let compact = Options::new().layout(crate::Layout::Compact);
+		let record = Type::of(&json!({ "a": 1 }));
+
+		assert_eq!(
+			record.declare("A", Dialect::Teal, &compact).unwrap(),
+			"local record A a: integer end return A"
+		);
+		assert_eq!(
+			record.declare("A", Dialect::Teal, &options).unwrap(),
+			"local record A\n\ta: integer\nend\n\nreturn A"
+		);
+
+		let names = [
+			("end", Dialect::Luau),
+			("number", Dialect::Luau),
+			("integer", Dialect::Teal),
+			("table", Dialect::LuaLS),
+		];
+
+		for (name, dialect) in names {
+			assert!(matches!(
+				record.declare(name, dialect, &options),
+				Err(Error::InvalidName(_))
+			));
+		}
+	}
+}